@@ -0,0 +1,33 @@
+use macroquad::prelude::*;
+use nalgebra::Vector2;
+use prism::shape::{Ball, Cuboid};
+use prism::Volume;
+
+#[macroquad::main("CSG")]
+async fn main() {
+    let center = Vector2::new(200.0, 200.0);
+    let area = (Cuboid::new(Vector2::new(100.0, 100.0)) + center)
+        .union(Ball::from_center(center + Vector2::new(100.0, 100.0), 70.0))
+        .subtract(Ball::from_center(center, 50.0));
+    let mut points = area.packed_points(5.0);
+    loop {
+        clear_background(BLACK);
+
+        if is_mouse_button_pressed(MouseButton::Left) {
+            points = area.packed_points(5.0);
+        }
+
+        draw_text(
+            &format!("Solver Iters: {}", points.iters),
+            10.0,
+            10.0,
+            20.0,
+            WHITE,
+        );
+
+        for v in points.iter() {
+            draw_circle(v.x, v.y, 5.0, Color::from_rgba(255, 255, 255, 255));
+        }
+        next_frame().await;
+    }
+}
@@ -1,10 +1,12 @@
 use super::*;
 
-// TODO: How to deal with repeating volumes?
 pub trait VolumeCore<const N: usize>: Sized + Sync {
     /// Should return the nearest point on the surface as well as whether the point is inside the shape.
-    /// Either this or both `distance` and `gradient` should be implemented.
+    /// Either this or both `distance` and `gradient` should be implemented, since the defaults are defined in
+    /// terms of each other. Debug builds panic instead of recursing forever when neither is.
     fn nearest_surface_point(&self, point: Vector<f32, N>) -> (Vector<f32, N>, bool) {
+        #[cfg(debug_assertions)]
+        let _guard = DefaultQueryGuard::enter(self);
         let dist = self.distance(point);
         let grad = self.gradient(point);
         (point - grad * dist, dist <= 0.0)
@@ -40,19 +42,34 @@ pub trait VolumeCore<const N: usize>: Sized + Sync {
     }
     fn min_bound(&self) -> Vector<f32, N>;
     fn max_bound(&self) -> Vector<f32, N>;
+    /// The period along each axis that the volume repeats with, or zero for axes where it doesn't repeat.
+    /// Along periodic axes, the bounds should be a single tile, of size equal to the period, which sampling
+    /// and packing treat as wrapping around. Volumes combined with CSG operations must have the same period.
+    /// See [`Periodic`](crate::periodic::Periodic).
+    fn period(&self) -> Vector<f32, N> {
+        Vector::zeros()
+    }
 
     fn create_grid(self, cell_size: f32) -> VolumeGrid<Self, N> {
         let offset = self.min_bound().map(|x| (x / cell_size).floor() as i32);
         let size = (self.max_bound().map(|x| (x / cell_size).ceil() as i32) - offset)
             .try_cast::<u32>()
             .unwrap();
+        let (min, max, period) = (self.min_bound(), self.max_bound(), self.period());
         let mut inside_cells = vec![];
         let mut border_cells = vec![];
         let cells = Array::from_fn(size, |pos| {
             let pos = pos.cast::<i32>() + offset;
             let point = (pos.cast::<f32>() + Vector::repeat(0.5)) * cell_size;
             let dist = self.distance(point) * std::f32::consts::SQRT_2 / cell_size;
+            // Cells sticking out of a periodic tile have to be checked, so that the tile isn't sampled twice.
+            let crosses_tile = (0..N).any(|i| {
+                period[i] > 0.0
+                    && (pos[i] as f32 * cell_size < min[i]
+                        || (pos[i] + 1) as f32 * cell_size > max[i])
+            });
             let ty = match dist {
+                ..-1.0 if crosses_tile => Cell::Border,
                 ..-1.0 => Cell::Inside,
                 -1.0..=1.0 => Cell::Border,
                 _ => Cell::Outside,
@@ -82,6 +99,9 @@ pub struct Array<T, const N: usize> {
 }
 
 impl<T, const N: usize> Array<T, N> {
+    pub fn shape(&self) -> Vector<u32, N> {
+        self.shape
+    }
     pub fn steps(&self) -> Vector<u32, N> {
         let mut steps = Vector::repeat(1);
         for (i, x) in self.shape.iter().enumerate() {
@@ -147,7 +167,7 @@ impl<V: VolumeCore<N>, const N: usize, R: Rng> Sampler<V, N, R> {
         match self.volume[point] {
             Cell::Inside => true,
             Cell::Outside => false,
-            Cell::Border => self.volume.contains(point),
+            Cell::Border => self.volume.accepts(point),
         }
     }
     pub fn sample_white(&mut self) -> Vector<f32, N> {
@@ -162,7 +182,7 @@ impl<V: VolumeCore<N>, const N: usize, R: Rng> Sampler<V, N, R> {
                 let point = point
                     + self.volume.border_cells[cell - self.volume.inside_cells.len()].cast::<f32>()
                         * self.volume.cell_size;
-                if self.volume.contains(point) {
+                if self.volume.accepts(point) {
                     return point;
                 }
             }
@@ -189,7 +209,7 @@ impl<V: VolumeCore<N>, const N: usize, R: Rng> Sampler<V, N, R> {
             for _ in 0..num_samples {
                 let point = Vector::from_fn(|_, _| self.rng.gen_range(0.0..self.volume.cell_size))
                     + cell.cast::<f32>() * self.volume.cell_size;
-                if self.volume.contains(point) {
+                if self.volume.accepts(point) {
                     f(point);
                 }
             }
@@ -218,7 +238,7 @@ impl<V: VolumeCore<N>, const N: usize, R: Rng> Sampler<V, N, R> {
                 cell.cast::<f32>() * self.volume.cell_size,
                 Vector::repeat(self.volume.cell_size),
                 |point| {
-                    if self.volume.contains(point) {
+                    if self.volume.accepts(point) {
                         f(point);
                     }
                 },
@@ -246,6 +266,17 @@ impl<V: VolumeCore<N>, const N: usize> VolumeGrid<V, N> {
     pub fn containing_cell(&self, point: Vector<f32, N>) -> Vector<i32, N> {
         (point / self.cell_size).map(|x| x.floor() as i32)
     }
+    /// Whether the point is in the volume, and inside the tile along periodic axes.
+    pub fn accepts(&self, point: Vector<f32, N>) -> bool {
+        let (min, max, period) = (self.min_bound(), self.max_bound(), self.period());
+        (0..N).all(|i| period[i] <= 0.0 || (point[i] >= min[i] && point[i] < max[i]))
+            && self.volume.contains(point)
+    }
+    /// Moves the point into the tile along periodic axes.
+    pub fn wrap(&self, point: Vector<f32, N>) -> Vector<f32, N> {
+        let (min, period) = (self.min_bound(), self.period());
+        Vector::from_fn(|i, _| wrap_coordinate(point[i], min[i], period[i]))
+    }
 }
 
 impl<V: VolumeCore<N>, const N: usize> Index<Vector<f32, N>> for VolumeGrid<V, N> {
@@ -268,6 +299,42 @@ impl<V: VolumeCore<N>, const N: usize> IndexMut<Vector<i32, N>> for VolumeGrid<V
     }
 }
 
+// Tracks the volumes whose default `nearest_surface_point` is running on this thread, to catch volumes that
+// implement neither it nor `distance` and `gradient`, whose defaults would otherwise recurse until the stack overflows.
+#[cfg(debug_assertions)]
+struct DefaultQueryGuard(usize, &'static str);
+#[cfg(debug_assertions)]
+thread_local! {
+    static DEFAULT_QUERIES: std::cell::RefCell<Vec<(usize, &'static str)>> = const { std::cell::RefCell::new(Vec::new()) };
+}
+#[cfg(debug_assertions)]
+impl DefaultQueryGuard {
+    fn enter<T>(volume: &T) -> Self {
+        // Wrappers can share the address of their first field, so the type is compared as well.
+        let key = (volume as *const T as usize, std::any::type_name::<T>());
+        DEFAULT_QUERIES.with_borrow_mut(|queries| {
+            assert!(
+                !queries.contains(&key),
+                "{} must implement either `nearest_surface_point` or both `distance` and `gradient`",
+                key.1
+            );
+            queries.push(key);
+        });
+        Self(key.0, key.1)
+    }
+}
+#[cfg(debug_assertions)]
+impl Drop for DefaultQueryGuard {
+    fn drop(&mut self) {
+        DEFAULT_QUERIES.with_borrow_mut(|queries| {
+            let key = (self.0, self.1);
+            if let Some(i) = queries.iter().rposition(|&k| k == key) {
+                queries.remove(i);
+            }
+        });
+    }
+}
+
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Cell {
@@ -275,3 +342,31 @@ pub enum Cell {
     Outside,
     Border,
 }
+
+// The recursion is only caught in debug builds.
+#[cfg(all(test, debug_assertions))]
+mod tests {
+    use nalgebra::Vector2;
+
+    use super::*;
+
+    // A disc which only implements the distance.
+    struct Disc;
+    impl VolumeCore<2> for Disc {
+        fn distance(&self, point: Vector2<f32>) -> f32 {
+            point.norm() - 1.0
+        }
+        fn min_bound(&self) -> Vector2<f32> {
+            Vector2::repeat(-1.0)
+        }
+        fn max_bound(&self) -> Vector2<f32> {
+            Vector2::repeat(1.0)
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "must implement either")]
+    fn distance_without_gradient() {
+        Disc.gradient(Vector2::new(2.0, 1.0));
+    }
+}
@@ -0,0 +1,152 @@
+use super::*;
+
+const LEAF_SIZE: usize = 4;
+
+#[derive(Debug, Clone, Copy)]
+pub struct BvhNode<const N: usize> {
+    pub min: Vector<f32, N>,
+    pub max: Vector<f32, N>,
+    // For leaves, the range of `Bvh::indices` covered. Otherwise, `start` is the index of the left child,
+    // and the right child follows it.
+    pub start: u32,
+    pub count: u32,
+}
+impl<const N: usize> BvhNode<N> {
+    pub fn is_leaf(&self) -> bool {
+        self.count > 0
+    }
+    pub fn distance(&self, point: Vector<f32, N>) -> f32 {
+        (point - point.zip_zip_map(&self.min, &self.max, |x, a, b| x.clamp(a, b))).norm()
+    }
+}
+
+/// A bounding volume hierarchy over a set of primitives, given by their bounding boxes.
+#[derive(Debug, Clone)]
+pub struct Bvh<const N: usize> {
+    pub nodes: Vec<BvhNode<N>>,
+    /// The indices of the primitives, ordered so that each leaf covers a contiguous range.
+    pub indices: Vec<u32>,
+}
+impl<const N: usize> Bvh<N> {
+    pub fn new(bounds: &[(Vector<f32, N>, Vector<f32, N>)]) -> Self {
+        let mut bvh = Bvh {
+            nodes: Vec::with_capacity(bounds.len().div_ceil(LEAF_SIZE) * 2),
+            indices: (0..bounds.len() as u32).collect(),
+        };
+        bvh.nodes.push(BvhNode {
+            min: Vector::repeat(f32::INFINITY),
+            max: Vector::repeat(f32::NEG_INFINITY),
+            start: 0,
+            count: 0,
+        });
+        if !bounds.is_empty() {
+            bvh.build(0, 0, bounds.len(), bounds);
+        }
+        bvh
+    }
+    fn build(
+        &mut self,
+        node: usize,
+        start: usize,
+        end: usize,
+        bounds: &[(Vector<f32, N>, Vector<f32, N>)],
+    ) {
+        let indices = &mut self.indices[start..end];
+        let (min, max) = indices.iter().fold(
+            (
+                Vector::<f32, N>::repeat(f32::INFINITY),
+                Vector::<f32, N>::repeat(f32::NEG_INFINITY),
+            ),
+            |(min, max), &i| {
+                let (a, b) = bounds[i as usize];
+                (min.inf(&a), max.sup(&b))
+            },
+        );
+        self.nodes[node].min = min;
+        self.nodes[node].max = max;
+        if indices.len() <= LEAF_SIZE {
+            self.nodes[node].start = start as u32;
+            self.nodes[node].count = indices.len() as u32;
+            return;
+        }
+        let axis = (max - min).imax();
+        let centroid = |i: u32| bounds[i as usize].0[axis] + bounds[i as usize].1[axis];
+        let mid = indices.len() / 2;
+        indices.select_nth_unstable_by(mid, |&a, &b| centroid(a).total_cmp(&centroid(b)));
+
+        let left = self.nodes.len();
+        self.nodes[node].start = left as u32;
+        self.nodes.push(self.nodes[node]);
+        self.nodes.push(self.nodes[node]);
+        self.build(left, start, start + mid, bounds);
+        self.build(left + 1, start + mid, end, bounds);
+    }
+
+    /// Calls `f` on every primitive in a leaf whose bounds pass `filter`.
+    pub fn visit(&self, mut filter: impl FnMut(&BvhNode<N>) -> bool, mut f: impl FnMut(u32)) {
+        if self.indices.is_empty() {
+            return;
+        }
+        let mut stack: SmallVec<[u32; 32]> = SmallVec::new();
+        stack.push(0);
+        while let Some(node) = stack.pop() {
+            let node = &self.nodes[node as usize];
+            if !filter(node) {
+                continue;
+            }
+            if node.is_leaf() {
+                for &i in &self.indices[node.start as usize..(node.start + node.count) as usize] {
+                    f(i);
+                }
+            } else {
+                stack.push(node.start);
+                stack.push(node.start + 1);
+            }
+        }
+    }
+
+    /// Finds the primitive minimizing `distance`, which must be bounded below by the distance to the primitive's bounds.
+    /// Ties are broken in favor of the primitive with the higher index, which matches a linear scan using `<=`.
+    pub fn nearest(
+        &self,
+        point: Vector<f32, N>,
+        mut distance: impl FnMut(u32) -> f32,
+    ) -> Option<(u32, f32)> {
+        if self.indices.is_empty() {
+            return None;
+        }
+        let mut best: Option<(u32, f32)> = None;
+        let mut stack: SmallVec<[(u32, f32); 32]> = SmallVec::new();
+        stack.push((0, self.nodes[0].distance(point)));
+        while let Some((node, node_dist)) = stack.pop() {
+            // Allow for some rounding error in the distances, so that ties are still found.
+            if best.is_some_and(|(_, d)| node_dist > d * (1.0 + 1e-5)) {
+                continue;
+            }
+            let node = &self.nodes[node as usize];
+            if node.is_leaf() {
+                for &i in &self.indices[node.start as usize..(node.start + node.count) as usize] {
+                    let dist = distance(i);
+                    if best.is_none_or(|(j, d)| dist < d || (dist == d && i > j)) {
+                        best = Some((i, dist));
+                    }
+                }
+            } else {
+                let left = (node.start, self.nodes[node.start as usize].distance(point));
+                let right = (
+                    node.start + 1,
+                    self.nodes[node.start as usize + 1].distance(point),
+                );
+                // Visit the closer child first.
+                if left.1 < right.1 {
+                    stack.push(right);
+                    stack.push(left);
+                } else {
+                    stack.push(left);
+                    stack.push(right);
+                }
+            }
+        }
+        best
+    }
+}
@@ -0,0 +1,365 @@
+use super::*;
+
+// The period of both volumes, which have to repeat the same way to be combined.
+fn shared_period<const N: usize>(a: &impl VolumeCore<N>, b: &impl VolumeCore<N>) -> Vector<f32, N> {
+    let period = a.period();
+    assert_eq!(
+        period,
+        b.period(),
+        "Combined volumes must have the same period"
+    );
+    period
+}
+
+// Replaces the bounds along periodic axes with those of a single tile.
+fn keep_tile<const N: usize>(
+    bound: Vector<f32, N>,
+    tile: Vector<f32, N>,
+    period: Vector<f32, N>,
+) -> Vector<f32, N> {
+    bound.zip_zip_map(&tile, &period, |x, t, p| if p > 0.0 { t } else { x })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Union<A, B> {
+    pub a: A,
+    pub b: B,
+}
+impl<A: VolumeCore<N>, B: VolumeCore<N>, const N: usize> VolumeCore<N> for Union<A, B> {
+    // Exact outside the shape, but may underestimate inside where the two volumes overlap.
+    fn distance(&self, point: Vector<f32, N>) -> f32 {
+        self.a.distance(point).min(self.b.distance(point))
+    }
+    fn gradient(&self, point: Vector<f32, N>) -> Vector<f32, N> {
+        if self.a.distance(point) <= self.b.distance(point) {
+            self.a.gradient(point)
+        } else {
+            self.b.gradient(point)
+        }
+    }
+    fn contains(&self, point: Vector<f32, N>) -> bool {
+        self.a.contains(point) || self.b.contains(point)
+    }
+    fn min_bound(&self) -> Vector<f32, N> {
+        let min = self.a.min_bound().inf(&self.b.min_bound());
+        keep_tile(min, self.a.min_bound(), self.period())
+    }
+    fn max_bound(&self) -> Vector<f32, N> {
+        let max = self.a.max_bound().sup(&self.b.max_bound());
+        keep_tile(max, self.a.max_bound(), self.period())
+    }
+    fn period(&self) -> Vector<f32, N> {
+        shared_period(&self.a, &self.b)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Intersection<A, B> {
+    pub a: A,
+    pub b: B,
+}
+impl<A: VolumeCore<N>, B: VolumeCore<N>, const N: usize> VolumeCore<N> for Intersection<A, B> {
+    // Exact inside the shape, but may underestimate outside near the corners.
+    fn distance(&self, point: Vector<f32, N>) -> f32 {
+        self.a.distance(point).max(self.b.distance(point))
+    }
+    fn gradient(&self, point: Vector<f32, N>) -> Vector<f32, N> {
+        if self.a.distance(point) >= self.b.distance(point) {
+            self.a.gradient(point)
+        } else {
+            self.b.gradient(point)
+        }
+    }
+    fn contains(&self, point: Vector<f32, N>) -> bool {
+        self.a.contains(point) && self.b.contains(point)
+    }
+    fn min_bound(&self) -> Vector<f32, N> {
+        let min = self.a.min_bound().sup(&self.b.min_bound());
+        keep_tile(min, self.a.min_bound(), self.period())
+    }
+    fn max_bound(&self) -> Vector<f32, N> {
+        // Keep the bounds non-inverted if the volumes don't overlap.
+        let max = self
+            .a
+            .max_bound()
+            .inf(&self.b.max_bound())
+            .sup(&self.min_bound());
+        keep_tile(max, self.a.max_bound(), self.period())
+    }
+    fn period(&self) -> Vector<f32, N> {
+        shared_period(&self.a, &self.b)
+    }
+}
+
+/// The volume `a` with `b` removed from it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Difference<A, B> {
+    pub a: A,
+    pub b: B,
+}
+impl<A: VolumeCore<N>, B: VolumeCore<N>, const N: usize> VolumeCore<N> for Difference<A, B> {
+    fn distance(&self, point: Vector<f32, N>) -> f32 {
+        self.a.distance(point).max(-self.b.distance(point))
+    }
+    fn gradient(&self, point: Vector<f32, N>) -> Vector<f32, N> {
+        if self.a.distance(point) >= -self.b.distance(point) {
+            self.a.gradient(point)
+        } else {
+            -self.b.gradient(point)
+        }
+    }
+    fn contains(&self, point: Vector<f32, N>) -> bool {
+        self.a.contains(point) && !self.b.contains(point)
+    }
+    fn min_bound(&self) -> Vector<f32, N> {
+        self.a.min_bound()
+    }
+    fn max_bound(&self) -> Vector<f32, N> {
+        self.a.max_bound()
+    }
+    fn period(&self) -> Vector<f32, N> {
+        shared_period(&self.a, &self.b)
+    }
+}
+
+/// The smooth minimum function used to fillet the joints of the smooth combinators.
+/// The parameter is the radius of the blend region.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Blend {
+    Polynomial(f32),
+    Exponential(f32),
+}
+impl Blend {
+    /// Returns the smoothed minimum of `a` and `b`, as well as the weight of `a` in the gradient.
+    /// Both variants are 1-Lipschitz, so the result is still a valid distance bound.
+    pub fn min(self, a: f32, b: f32) -> (f32, f32) {
+        match self {
+            Blend::Polynomial(k) => {
+                if k <= 0.0 {
+                    return if a <= b { (a, 1.0) } else { (b, 0.0) };
+                }
+                let h = (0.5 + 0.5 * (b - a) / k).clamp(0.0, 1.0);
+                (b + (a - b) * h - k * h * (1.0 - h), h)
+            }
+            Blend::Exponential(k) => {
+                if k <= 0.0 {
+                    return if a <= b { (a, 1.0) } else { (b, 0.0) };
+                }
+                let m = a.min(b);
+                let ea = (-(a - m) / k).exp();
+                let eb = (-(b - m) / k).exp();
+                (m - k * (ea + eb).ln(), ea / (ea + eb))
+            }
+        }
+    }
+    pub fn max(self, a: f32, b: f32) -> (f32, f32) {
+        let (value, weight) = self.min(-a, -b);
+        (-value, weight)
+    }
+    /// The maximum distance the smoothed surface can extend past the sharp one.
+    pub fn expansion(self) -> f32 {
+        match self {
+            Blend::Polynomial(k) => k.max(0.0) / 4.0,
+            Blend::Exponential(k) => k.max(0.0) * std::f32::consts::LN_2,
+        }
+    }
+}
+
+fn blend_gradient<const N: usize>(
+    ga: Vector<f32, N>,
+    gb: Vector<f32, N>,
+    weight: f32,
+) -> Vector<f32, N> {
+    (ga * weight + gb * (1.0 - weight))
+        .try_normalize(1e-6)
+        .unwrap_or(Vector::zeros())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SmoothUnion<A, B> {
+    pub a: A,
+    pub b: B,
+    pub blend: Blend,
+}
+impl<A: VolumeCore<N>, B: VolumeCore<N>, const N: usize> VolumeCore<N> for SmoothUnion<A, B> {
+    fn distance(&self, point: Vector<f32, N>) -> f32 {
+        self.blend
+            .min(self.a.distance(point), self.b.distance(point))
+            .0
+    }
+    fn gradient(&self, point: Vector<f32, N>) -> Vector<f32, N> {
+        let (_, weight) = self
+            .blend
+            .min(self.a.distance(point), self.b.distance(point));
+        blend_gradient(self.a.gradient(point), self.b.gradient(point), weight)
+    }
+    fn contains(&self, point: Vector<f32, N>) -> bool {
+        self.distance(point) <= 0.0
+    }
+    fn min_bound(&self) -> Vector<f32, N> {
+        let min =
+            self.a.min_bound().inf(&self.b.min_bound()) - Vector::repeat(self.blend.expansion());
+        keep_tile(min, self.a.min_bound(), self.period())
+    }
+    fn max_bound(&self) -> Vector<f32, N> {
+        let max =
+            self.a.max_bound().sup(&self.b.max_bound()) + Vector::repeat(self.blend.expansion());
+        keep_tile(max, self.a.max_bound(), self.period())
+    }
+    fn period(&self) -> Vector<f32, N> {
+        shared_period(&self.a, &self.b)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SmoothIntersection<A, B> {
+    pub a: A,
+    pub b: B,
+    pub blend: Blend,
+}
+impl<A: VolumeCore<N>, B: VolumeCore<N>, const N: usize> VolumeCore<N>
+    for SmoothIntersection<A, B>
+{
+    fn distance(&self, point: Vector<f32, N>) -> f32 {
+        self.blend
+            .max(self.a.distance(point), self.b.distance(point))
+            .0
+    }
+    fn gradient(&self, point: Vector<f32, N>) -> Vector<f32, N> {
+        let (_, weight) = self
+            .blend
+            .max(self.a.distance(point), self.b.distance(point));
+        blend_gradient(self.a.gradient(point), self.b.gradient(point), weight)
+    }
+    fn contains(&self, point: Vector<f32, N>) -> bool {
+        self.distance(point) <= 0.0
+    }
+    // The smoothed intersection is always contained in the sharp one.
+    fn min_bound(&self) -> Vector<f32, N> {
+        let min = self.a.min_bound().sup(&self.b.min_bound());
+        keep_tile(min, self.a.min_bound(), self.period())
+    }
+    fn max_bound(&self) -> Vector<f32, N> {
+        let max = self
+            .a
+            .max_bound()
+            .inf(&self.b.max_bound())
+            .sup(&self.min_bound());
+        keep_tile(max, self.a.max_bound(), self.period())
+    }
+    fn period(&self) -> Vector<f32, N> {
+        shared_period(&self.a, &self.b)
+    }
+}
+
+/// The volume `a` with `b` removed from it, with the cut edges filleted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SmoothDifference<A, B> {
+    pub a: A,
+    pub b: B,
+    pub blend: Blend,
+}
+impl<A: VolumeCore<N>, B: VolumeCore<N>, const N: usize> VolumeCore<N> for SmoothDifference<A, B> {
+    fn distance(&self, point: Vector<f32, N>) -> f32 {
+        self.blend
+            .max(self.a.distance(point), -self.b.distance(point))
+            .0
+    }
+    fn gradient(&self, point: Vector<f32, N>) -> Vector<f32, N> {
+        let (_, weight) = self
+            .blend
+            .max(self.a.distance(point), -self.b.distance(point));
+        blend_gradient(self.a.gradient(point), -self.b.gradient(point), weight)
+    }
+    fn contains(&self, point: Vector<f32, N>) -> bool {
+        self.distance(point) <= 0.0
+    }
+    fn min_bound(&self) -> Vector<f32, N> {
+        self.a.min_bound()
+    }
+    fn max_bound(&self) -> Vector<f32, N> {
+        self.a.max_bound()
+    }
+    fn period(&self) -> Vector<f32, N> {
+        shared_period(&self.a, &self.b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector2;
+    use shape::Ball;
+
+    use super::*;
+
+    #[test]
+    fn periodic_operands() {
+        let period = Vector2::new(4.0, 0.0);
+        let a = Ball::new(1.0).periodic(period);
+        let b = (Ball::new(1.0) + Vector2::new(3.0, 0.5)).periodic(period);
+        let union = a.union(b);
+        assert_eq!(union.period(), period);
+        // Along the periodic axis, the bounds are still a single tile.
+        assert_eq!(union.min_bound(), Vector2::new(-2.0, -1.0));
+        assert_eq!(union.max_bound(), Vector2::new(2.0, 1.5));
+        let blended = a.smooth_subtract(b, Blend::Polynomial(0.5));
+        assert_eq!(blended.period(), period);
+    }
+
+    #[test]
+    fn smooth_blends() {
+        let a = Ball::new(1.0) - Vector2::new(0.6, 0.0);
+        let b = Ball::new(1.0) + Vector2::new(0.6, 0.0);
+        let mut rng = Pcg64Mcg::seed_from_u64(5);
+        for blend in [Blend::Polynomial(0.3), Blend::Exponential(0.3)] {
+            let expansion = blend.expansion();
+            let union = (a.union(b), a.smooth_union(b, blend));
+            let intersection = (a.intersect(b), a.smooth_intersect(b, blend));
+            let difference = (a.subtract(b), a.smooth_subtract(b, blend));
+            for _ in 0..2000 {
+                let point = Vector2::new(rng.gen_range(-3.0..3.0), rng.gen_range(-3.0..3.0));
+                let other =
+                    point + Vector2::new(rng.gen_range(-0.05..0.05), rng.gen_range(-0.05..0.05));
+                let (da, db) = (a.distance(point), b.distance(point));
+                // Intersections and differences are negated, so that they are blended minimums too.
+                for (sharp, smooth, seam) in [
+                    (union.0.distance(point), union.1.distance(point), da - db),
+                    (
+                        -intersection.0.distance(point),
+                        -intersection.1.distance(point),
+                        da - db,
+                    ),
+                    (
+                        -difference.0.distance(point),
+                        -difference.1.distance(point),
+                        da + db,
+                    ),
+                ] {
+                    // The blended minimum is below the sharp one by at most the expansion.
+                    assert!(smooth <= sharp + 1e-6, "{blend:?} {point}");
+                    assert!(smooth >= sharp - expansion - 1e-6, "{blend:?} {point}");
+                    // Far from the seam, the blend makes no difference.
+                    if seam.abs() > 1.5 {
+                        assert!((smooth - sharp).abs() < 2e-3, "{blend:?} {point}");
+                    }
+                }
+                // Within the blend, the distances are still 1-Lipschitz, so they don't overestimate.
+                let step = (point - other).norm() * (1.0 + 1e-3) + 1e-6;
+                assert!((union.1.distance(point) - union.1.distance(other)).abs() <= step);
+                assert!(
+                    (intersection.1.distance(point) - intersection.1.distance(other)).abs() <= step
+                );
+                assert!(
+                    (difference.1.distance(point) - difference.1.distance(other)).abs() <= step
+                );
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "same period")]
+    fn mismatched_periods() {
+        let a = Ball::new(1.0).periodic(Vector2::new(4.0, 0.0));
+        a.intersect(Ball::<2>::new(1.0)).period();
+    }
+}
@@ -1,14 +1,120 @@
-use shape::Extrude3;
+use csg::{
+    Blend, Difference, Intersection, SmoothDifference, SmoothIntersection, SmoothUnion, Union,
+};
+use morphology::{Closing, Convex, MinkowskiSum, Opening};
+use nalgebra::{Rotation, SMatrix, Vector3};
+use periodic::Periodic;
+use shape::{Extrude3, ExtrudeWarp, LinearWarp, Revolve3, WarpedExtrude3};
+use sweep::Sweep;
+use transform::Transformed;
 
 use super::*;
 
 pub trait Volume<const N: usize>: VolumeCore<N> {
+    /// Shifts the distance by `offset`, so positive offsets shrink the volume and negative offsets grow it.
     fn pad(self, offset: f32) -> PaddedVolume<Self, N> {
         PaddedVolume {
             offset,
             field: self,
         }
     }
+    /// The points within `thickness / 2` of the surface.
+    fn shell(self, thickness: f32) -> Shell<Self, N> {
+        Shell {
+            center: 0.0,
+            half_thickness: thickness / 2.0,
+            field: self,
+        }
+    }
+    /// The points inside the volume within `thickness` of the surface.
+    fn inner_shell(self, thickness: f32) -> Shell<Self, N> {
+        Shell {
+            center: -thickness / 2.0,
+            half_thickness: thickness / 2.0,
+            field: self,
+        }
+    }
+    /// The points outside the volume within `thickness` of the surface.
+    fn outer_shell(self, thickness: f32) -> Shell<Self, N> {
+        Shell {
+            center: thickness / 2.0,
+            half_thickness: thickness / 2.0,
+            field: self,
+        }
+    }
+    /// Grows the volume by a convex shape, such as a [`Cuboid`](crate::shape::Cuboid) or [`Capsule`](crate::shape::Capsule).
+    fn minkowski_sum<B: Convex<N>>(self, other: B) -> MinkowskiSum<Self, B> {
+        MinkowskiSum { a: self, b: other }
+    }
+    /// Removes the parts of the volume narrower than `2 * radius`.
+    fn opening(self, radius: f32) -> Opening<Self> {
+        Opening {
+            volume: self,
+            radius,
+        }
+    }
+    /// Fills the gaps in the volume narrower than `2 * radius`.
+    fn closing(self, radius: f32) -> Closing<Self> {
+        Closing {
+            volume: self,
+            radius,
+        }
+    }
+    fn union<B: VolumeCore<N>>(self, other: B) -> Union<Self, B> {
+        Union { a: self, b: other }
+    }
+    fn intersect<B: VolumeCore<N>>(self, other: B) -> Intersection<Self, B> {
+        Intersection { a: self, b: other }
+    }
+    fn subtract<B: VolumeCore<N>>(self, other: B) -> Difference<Self, B> {
+        Difference { a: self, b: other }
+    }
+    fn transform(
+        self,
+        matrix: SMatrix<f32, N, N>,
+        translation: Vector<f32, N>,
+    ) -> Transformed<Self, N> {
+        Transformed::new(self, matrix, translation)
+    }
+    fn rotate(self, rotation: Rotation<f32, N>) -> Transformed<Self, N> {
+        Transformed::from_rotation(self, rotation)
+    }
+    fn scale(self, scale: Vector<f32, N>) -> Transformed<Self, N> {
+        Transformed::new(self, SMatrix::from_diagonal(&scale), Vector::zeros())
+    }
+    /// Repeats the volume with the given period, where a period of zero means the axis doesn't repeat.
+    fn periodic(self, period: Vector<f32, N>) -> Periodic<Self, N> {
+        Periodic::new(self, period)
+    }
+    fn smooth_union<B: VolumeCore<N>>(self, other: B, blend: Blend) -> SmoothUnion<Self, B> {
+        SmoothUnion {
+            a: self,
+            b: other,
+            blend,
+        }
+    }
+    fn smooth_intersect<B: VolumeCore<N>>(
+        self,
+        other: B,
+        blend: Blend,
+    ) -> SmoothIntersection<Self, B> {
+        SmoothIntersection {
+            a: self,
+            b: other,
+            blend,
+        }
+    }
+    fn smooth_subtract<B: VolumeCore<N>>(
+        self,
+        other: B,
+        blend: Blend,
+    ) -> SmoothDifference<Self, B> {
+        SmoothDifference {
+            a: self,
+            b: other,
+            blend,
+        }
+    }
     fn grid_points(self, settings: impl Into<GridSettings<N>>) -> Vec<Vector<f32, N>> {
         let settings = settings.into();
         if settings.border_adjust_radius != 0.0 {
@@ -148,11 +254,63 @@ impl<V: VolumeCore<N>, const N: usize> VolumeCore<N> for PaddedVolume<V, N> {
     fn contains(&self, point: Vector<f32, N>) -> bool {
         self.distance(point) <= 0.0
     }
+    // Periodic tiles are kept as they are.
     fn min_bound(&self) -> Vector<f32, N> {
-        self.field.min_bound() + Vector::repeat(self.offset)
+        let period = self.field.period();
+        self.field
+            .min_bound()
+            .zip_map(&period, |x, p| if p > 0.0 { x } else { x + self.offset })
     }
     fn max_bound(&self) -> Vector<f32, N> {
-        self.field.max_bound() - Vector::repeat(self.offset)
+        let period = self.field.period();
+        self.field
+            .max_bound()
+            .zip_map(&period, |x, p| if p > 0.0 { x } else { x - self.offset })
+    }
+    fn period(&self) -> Vector<f32, N> {
+        self.field.period()
+    }
+}
+
+/// A band around the surface of a volume, made of the points whose distance is within `half_thickness` of `center`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Shell<V: VolumeCore<N>, const N: usize> {
+    center: f32,
+    half_thickness: f32,
+    field: V,
+}
+impl<V: VolumeCore<N>, const N: usize> VolumeCore<N> for Shell<V, N> {
+    fn distance(&self, point: Vector<f32, N>) -> f32 {
+        (self.field.distance(point) - self.center).abs() - self.half_thickness
+    }
+    // Points on the inner side of the band move away from the surface to leave it.
+    fn gradient(&self, point: Vector<f32, N>) -> Vector<f32, N> {
+        if self.field.distance(point) < self.center {
+            -self.field.gradient(point)
+        } else {
+            self.field.gradient(point)
+        }
+    }
+    fn contains(&self, point: Vector<f32, N>) -> bool {
+        self.distance(point) <= 0.0
+    }
+    // The band only reaches outside the volume when its outer edge does.
+    fn min_bound(&self) -> Vector<f32, N> {
+        let reach = (self.center + self.half_thickness).max(0.0);
+        let period = self.field.period();
+        self.field
+            .min_bound()
+            .zip_map(&period, |x, p| if p > 0.0 { x } else { x - reach })
+    }
+    fn max_bound(&self) -> Vector<f32, N> {
+        let reach = (self.center + self.half_thickness).max(0.0);
+        let period = self.field.period();
+        self.field
+            .max_bound()
+            .zip_map(&period, |x, p| if p > 0.0 { x } else { x + reach })
+    }
+    fn period(&self) -> Vector<f32, N> {
+        self.field.period()
     }
 }
 
@@ -228,5 +386,57 @@ pub trait Volume2d: VolumeCore<2> {
             base: self,
         }
     }
+    /// Extrudes the shape, scaling it linearly from `start_scale` at `start` to `end_scale` at `end`.
+    fn extrude_tapered(
+        self,
+        start: f32,
+        end: f32,
+        start_scale: f32,
+        end_scale: f32,
+    ) -> WarpedExtrude3<Self, LinearWarp> {
+        self.extrude_warped(
+            start,
+            end,
+            LinearWarp {
+                heights: (start, end),
+                scale: (start_scale, end_scale),
+                twist: (0.0, 0.0),
+            },
+        )
+    }
+    /// Extrudes the shape, twisting it counterclockwise around the z axis by `angle` between `start` and `end`.
+    fn extrude_twisted(self, start: f32, end: f32, angle: f32) -> WarpedExtrude3<Self, LinearWarp> {
+        self.extrude_warped(
+            start,
+            end,
+            LinearWarp {
+                heights: (start, end),
+                scale: (1.0, 1.0),
+                twist: (0.0, angle),
+            },
+        )
+    }
+    fn extrude_warped<W: ExtrudeWarp>(
+        self,
+        start: f32,
+        end: f32,
+        warp: W,
+    ) -> WarpedExtrude3<Self, W> {
+        WarpedExtrude3::new(self, (start, end), warp)
+    }
+    /// Sweeps the shape along straight lines between the points, see [`Sweep`].
+    fn sweep(self, points: &[Vector3<f32>]) -> Sweep<Self> {
+        Sweep::along_polyline(self, points)
+    }
+    /// Sweeps the shape along a Catmull-Rom spline through the points, see [`Sweep`].
+    fn sweep_spline(self, points: &[Vector3<f32>]) -> Sweep<Self> {
+        Sweep::along_spline(self, points)
+    }
+    fn revolve(self) -> Revolve3<Self> {
+        self.revolve_partial(std::f32::consts::TAU)
+    }
+    fn revolve_partial(self, angle: f32) -> Revolve3<Self> {
+        Revolve3 { base: self, angle }
+    }
 }
 impl<X> Volume2d for X where X: VolumeCore<2> {}
@@ -0,0 +1,209 @@
+use nalgebra::Vector3;
+
+use super::*;
+
+/// Bounds on a [`HeightMap`] over a footprint, used to keep the distance conservative.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HeightLimits {
+    pub low: f32,
+    pub high: f32,
+    /// The largest norm of the gradient of the height.
+    pub max_slope: f32,
+}
+
+/// The height of a terrain at each point of the xy plane.
+/// Closures taking the point and returning the height implement this.
+pub trait HeightMap: Sync {
+    fn height(&self, point: Vector2<f32>) -> f32;
+    /// The default estimates the limits by sampling, so it can miss features smaller than the sample spacing.
+    fn limits(&self, min: Vector2<f32>, max: Vector2<f32>) -> HeightLimits {
+        const SAMPLES: u32 = 256;
+        let step = (max - min) / SAMPLES as f32;
+        let heights = Array::from_fn(Vector2::repeat(SAMPLES + 1), |index| {
+            self.height(min + index.cast::<f32>().component_mul(&step))
+        });
+        let limits = grid_limits(&heights, step);
+        // Heights between the samples can go past the sampled ones by at most this much.
+        let margin = limits.max_slope * step.norm() / 2.0;
+        HeightLimits {
+            low: limits.low - margin,
+            high: limits.high + margin,
+            ..limits
+        }
+    }
+}
+impl<F: Fn(Vector2<f32>) -> f32 + Sync> HeightMap for F {
+    fn height(&self, point: Vector2<f32>) -> f32 {
+        self(point)
+    }
+}
+
+/// Heights sampled on a regular grid spanning a rectangle, which are interpolated bilinearly.
+#[derive(Debug, Clone)]
+pub struct HeightGrid {
+    samples: Array<f32, 2>,
+    min: Vector2<f32>,
+    max: Vector2<f32>,
+}
+impl HeightGrid {
+    /// Creates a grid where the corner samples are at `min` and `max`.
+    /// Panics if there are less than two samples along either axis.
+    pub fn new(samples: Array<f32, 2>, min: Vector2<f32>, max: Vector2<f32>) -> Self {
+        assert!(
+            samples.shape().iter().all(|&x| x >= 2),
+            "HeightGrid needs at least two samples along each axis"
+        );
+        Self { samples, min, max }
+    }
+    pub fn samples(&self) -> &Array<f32, 2> {
+        &self.samples
+    }
+    fn step(&self) -> Vector2<f32> {
+        (self.max - self.min).component_div(&(self.samples.shape() - Vector2::repeat(1)).cast())
+    }
+}
+impl HeightMap for HeightGrid {
+    fn height(&self, point: Vector2<f32>) -> f32 {
+        let shape = self.samples.shape();
+        let local = (point - self.min).component_div(&self.step());
+        let cell = local.zip_map(&shape, |x, s| (x.floor().max(0.0) as u32).min(s - 2));
+        let t = local.zip_map(&cell, |x, c| (x - c as f32).clamp(0.0, 1.0));
+        let sample = |x: u32, y: u32| self.samples[cell + Vector2::new(x, y)];
+        let low = sample(0, 0) * (1.0 - t.x) + sample(1, 0) * t.x;
+        let high = sample(0, 1) * (1.0 - t.x) + sample(1, 1) * t.x;
+        low * (1.0 - t.y) + high * t.y
+    }
+    // Bilinear interpolation is steepest along the edges of the cells, so the limits are exact.
+    fn limits(&self, _min: Vector2<f32>, _max: Vector2<f32>) -> HeightLimits {
+        grid_limits(&self.samples, self.step())
+    }
+}
+
+fn grid_limits(heights: &Array<f32, 2>, step: Vector2<f32>) -> HeightLimits {
+    let shape = heights.shape();
+    let mut limits = HeightLimits {
+        low: f32::INFINITY,
+        high: f32::NEG_INFINITY,
+        max_slope: 0.0,
+    };
+    let mut max_rate = Vector2::<f32>::zeros();
+    for x in 0..shape.x {
+        for y in 0..shape.y {
+            let h = heights[Vector2::new(x, y)];
+            limits.low = limits.low.min(h);
+            limits.high = limits.high.max(h);
+            if x + 1 < shape.x && step.x > 0.0 {
+                let rate = (heights[Vector2::new(x + 1, y)] - h).abs() / step.x;
+                max_rate.x = max_rate.x.max(rate);
+            }
+            if y + 1 < shape.y && step.y > 0.0 {
+                let rate = (heights[Vector2::new(x, y + 1)] - h).abs() / step.y;
+                max_rate.y = max_rate.y.max(rate);
+            }
+        }
+    }
+    limits.max_slope = max_rate.norm();
+    limits
+}
+
+/// The region below a height map over a rectangular footprint, and above a floor.
+/// Without a floor, the region is cut off at the lowest height of the map.
+///
+/// The distance to the terrain is the vertical distance scaled by the largest slope,
+/// so it is underestimated on gentle parts of steep terrain.
+#[derive(Debug, Clone)]
+pub struct Heightfield<H: HeightMap> {
+    map: H,
+    min: Vector2<f32>,
+    max: Vector2<f32>,
+    floor: f32,
+    limits: HeightLimits,
+    epsilon: f32,
+}
+impl<H: HeightMap> Heightfield<H> {
+    pub fn new(map: H, min: Vector2<f32>, max: Vector2<f32>) -> Self {
+        let limits = map.limits(min, max);
+        Self {
+            map,
+            min,
+            max,
+            floor: limits.low,
+            limits,
+            epsilon: ((max - min).amax() * 1e-4).max(1e-6),
+        }
+    }
+    pub fn with_floor(self, floor: f32) -> Self {
+        Self { floor, ..self }
+    }
+    pub fn map(&self) -> &H {
+        &self.map
+    }
+    pub fn floor(&self) -> f32 {
+        self.floor
+    }
+    pub fn limits(&self) -> HeightLimits {
+        self.limits
+    }
+    // Returns the distance and gradient.
+    fn query(&self, point: Vector3<f32>) -> (f32, Vector3<f32>) {
+        // Outside the footprint, the height is extended from its edge, which keeps the slope the same.
+        let xy = point
+            .xy()
+            .zip_zip_map(&self.min, &self.max, |x, a, b| x.clamp(a, b));
+        let height = |p: Vector2<f32>| self.map.height(p);
+        let scale = (1.0 + self.limits.max_slope * self.limits.max_slope).sqrt();
+        let slope = central_difference(height, xy, self.epsilon);
+        let mut best = (
+            (point.z - height(xy)) / scale,
+            Vector3::new(-slope.x, -slope.y, 1.0).normalize(),
+        );
+
+        let center = (self.min + self.max) / 2.0;
+        let q = (point.xy() - center).abs() - (self.max - self.min) / 2.0;
+        let side_dist = q.sup(&Vector2::zeros()).norm() + q.max().min(0.0);
+        if side_dist > best.0 {
+            let side_grad = if q.max() > 0.0 {
+                q.sup(&Vector2::zeros()).normalize()
+            } else if q.x > q.y {
+                Vector2::x()
+            } else {
+                Vector2::y()
+            };
+            let side_grad = side_grad.component_mul(&(point.xy() - center).map(|x| x.signum()));
+            best = (side_dist, side_grad.push(0.0));
+        }
+
+        let floor_dist = self.floor - point.z;
+        if floor_dist > best.0 {
+            best = (floor_dist, -Vector3::z());
+        }
+        best
+    }
+}
+
+impl<H: HeightMap> VolumeCore<3> for Heightfield<H> {
+    fn distance(&self, point: Vector3<f32>) -> f32 {
+        self.query(point).0
+    }
+    fn gradient(&self, point: Vector3<f32>) -> Vector3<f32> {
+        self.query(point).1
+    }
+    fn contains(&self, point: Vector3<f32>) -> bool {
+        point.xy() >= self.min
+            && point.xy() <= self.max
+            && point.z >= self.floor
+            && point.z <= self.map.height(point.xy())
+    }
+    fn min_bound(&self) -> Vector3<f32> {
+        self.min.push(self.floor)
+    }
+    fn max_bound(&self) -> Vector3<f32> {
+        self.max.push(self.limits.high)
+    }
+}
+
+impl Heightfield<HeightGrid> {
+    pub fn from_grid(samples: Array<f32, 2>, min: Vector2<f32>, max: Vector2<f32>) -> Self {
+        Self::new(HeightGrid::new(samples, min, max), min, max)
+    }
+}
@@ -0,0 +1,372 @@
+use std::collections::hash_map::Entry;
+use std::ops::{Add, Sub};
+
+use morphology::Convex;
+use nalgebra::DMatrix;
+use polytope::{ConvexPolytope, HalfSpace, PolytopeError};
+
+use super::*;
+
+/// The convex hull of a set of points, found with quickhull.
+#[derive(Debug, Clone)]
+pub struct ConvexHull<const N: usize> {
+    polytope: ConvexPolytope<N>,
+}
+impl<const N: usize> ConvexHull<N> {
+    /// Fails with [`PolytopeError::Flat`] if the points all lie in a hyperplane, or there are none.
+    pub fn new(points: &[Vector<f32, N>]) -> Result<Self, PolytopeError> {
+        let points = points.iter().map(|p| p.cast::<f64>()).collect::<Vec<_>>();
+        let (min, max) = points.iter().fold(
+            (
+                Vector::<f64, N>::repeat(f64::INFINITY),
+                Vector::<f64, N>::repeat(f64::NEG_INFINITY),
+            ),
+            |(min, max), p| (min.inf(p), max.sup(p)),
+        );
+        // The points come from `f32`, so anything closer than their rounding is considered coplanar.
+        let epsilon = 1e-6 * ((max - min).amax() + min.amax().max(max.amax()));
+        let facets = quickhull(&points, epsilon).ok_or(PolytopeError::Flat)?;
+
+        // Faces with more than `N` vertices are split into several facets, which are merged again.
+        let mut faces: Vec<&Facet<N>> = vec![];
+        for facet in &facets {
+            if !faces.iter().any(|face| {
+                face.normal.dot(&facet.normal) > 0.0
+                    && facet
+                        .vertices
+                        .iter()
+                        .all(|&i| face.height(points[i]).abs() <= epsilon)
+            }) {
+                faces.push(facet);
+            }
+        }
+        let mut is_vertex = vec![false; points.len()];
+        for &i in facets.iter().flat_map(|f| &f.vertices) {
+            is_vertex[i] = true;
+        }
+        // Points inside a face are only on one of them, while corners are on at least `N`.
+        let vertices = (0..points.len())
+            .filter(|&i| {
+                is_vertex[i]
+                    && faces
+                        .iter()
+                        .filter(|face| face.height(points[i]).abs() <= epsilon)
+                        .count()
+                        >= N
+            })
+            .map(|i| points[i].cast::<f32>())
+            .collect();
+        let half_spaces = faces
+            .iter()
+            .map(|face| HalfSpace::new(face.normal.cast::<f32>(), face.offset as f32))
+            .collect();
+        Ok(ConvexHull {
+            polytope: ConvexPolytope::from_parts(half_spaces, vertices),
+        })
+    }
+    pub fn polytope(&self) -> &ConvexPolytope<N> {
+        &self.polytope
+    }
+    pub fn into_polytope(self) -> ConvexPolytope<N> {
+        self.polytope
+    }
+    /// The points on the corners of the hull.
+    pub fn vertices(&self) -> &[Vector<f32, N>] {
+        self.polytope.vertices()
+    }
+    pub fn half_spaces(&self) -> &[HalfSpace<N>] {
+        self.polytope.half_spaces()
+    }
+    pub fn offset(self, offset: Vector<f32, N>) -> Self {
+        ConvexHull {
+            polytope: self.polytope.offset(offset),
+        }
+    }
+}
+impl<const N: usize> Convex<N> for ConvexHull<N> {
+    fn support(&self, direction: Vector<f32, N>) -> Vector<f32, N> {
+        self.polytope.support(direction)
+    }
+}
+impl<const N: usize> Add<Vector<f32, N>> for ConvexHull<N> {
+    type Output = Self;
+    fn add(self, rhs: Vector<f32, N>) -> Self::Output {
+        self.offset(rhs)
+    }
+}
+impl<const N: usize> Sub<Vector<f32, N>> for ConvexHull<N> {
+    type Output = Self;
+    fn sub(self, rhs: Vector<f32, N>) -> Self::Output {
+        self.offset(-rhs)
+    }
+}
+impl<const N: usize> VolumeCore<N> for ConvexHull<N> {
+    fn distance(&self, point: Vector<f32, N>) -> f32 {
+        self.polytope.distance(point)
+    }
+    fn gradient(&self, point: Vector<f32, N>) -> Vector<f32, N> {
+        self.polytope.gradient(point)
+    }
+    fn contains(&self, point: Vector<f32, N>) -> bool {
+        self.polytope.contains(point)
+    }
+    fn min_bound(&self) -> Vector<f32, N> {
+        self.polytope.min_bound()
+    }
+    fn max_bound(&self) -> Vector<f32, N> {
+        self.polytope.max_bound()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Facet<const N: usize> {
+    vertices: SmallVec<[usize; 4]>,
+    normal: Vector<f64, N>,
+    offset: f64,
+    // The points above this facet which haven't been handled yet.
+    outside: Vec<usize>,
+    alive: bool,
+}
+impl<const N: usize> Facet<N> {
+    fn height(&self, point: Vector<f64, N>) -> f64 {
+        self.normal.dot(&point) - self.offset
+    }
+}
+
+// Returns the facets of the hull, or `None` if the points don't span every dimension.
+fn quickhull<const N: usize>(points: &[Vector<f64, N>], epsilon: f64) -> Option<Vec<Facet<N>>> {
+    let simplex = initial_simplex(points, epsilon)?;
+    let center = simplex.iter().map(|&i| points[i]).sum::<Vector<f64, N>>() / (N + 1) as f64;
+    let mut facets = vec![];
+    for skip in 0..=N {
+        let vertices = simplex
+            .iter()
+            .enumerate()
+            .filter(|&(j, _)| j != skip)
+            .map(|(_, &i)| i)
+            .collect();
+        facets.push(make_facet(points, vertices, center)?);
+    }
+    assign(points, &mut facets, 0, 0..points.len(), epsilon);
+
+    // The facets which had outside points when they were created. Facets which have been replaced since are skipped.
+    let mut pending = (0..facets.len())
+        .filter(|&i| !facets[i].outside.is_empty())
+        .collect::<Vec<_>>();
+    while let Some(current) = pending.pop() {
+        let facet = &facets[current];
+        if !facet.alive {
+            continue;
+        }
+        let eye = *facet
+            .outside
+            .iter()
+            .max_by(|&&a, &&b| facet.height(points[a]).total_cmp(&facet.height(points[b])))
+            .unwrap();
+        // The facets the eye can see form a connected region, whose boundary is the horizon.
+        let visible = (0..facets.len())
+            .filter(|&i| facets[i].alive && facets[i].height(points[eye]) > epsilon)
+            .collect::<Vec<_>>();
+        let mut ridges = HashMap::new();
+        for &i in &visible {
+            for skip in 0..N {
+                let ridge = facets[i]
+                    .vertices
+                    .iter()
+                    .enumerate()
+                    .filter(|&(j, _)| j != skip)
+                    .map(|(_, &v)| v)
+                    .collect::<SmallVec<[usize; 4]>>();
+                let mut key = ridge.clone();
+                key.sort_unstable();
+                match ridges.entry(key) {
+                    Entry::Occupied(entry) => {
+                        entry.remove();
+                    }
+                    Entry::Vacant(entry) => {
+                        entry.insert(ridge);
+                    }
+                }
+            }
+        }
+        let mut orphans = vec![];
+        for &i in &visible {
+            facets[i].alive = false;
+            orphans.append(&mut facets[i].outside);
+        }
+        // Sorted so that the result doesn't depend on the hash order.
+        let mut ridges = ridges.into_iter().collect::<Vec<_>>();
+        ridges.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        let first = facets.len();
+        for (_, mut ridge) in ridges {
+            ridge.push(eye);
+            facets.push(make_facet(points, ridge, center)?);
+        }
+        orphans.retain(|&i| i != eye);
+        assign(points, &mut facets, first, orphans.into_iter(), epsilon);
+        pending.extend((first..facets.len()).filter(|&i| !facets[i].outside.is_empty()));
+    }
+    facets.retain(|f| f.alive);
+    Some(facets)
+}
+
+// Finds `N + 1` points which are as far from each other as possible, to start the hull with.
+fn initial_simplex<const N: usize>(
+    points: &[Vector<f64, N>],
+    epsilon: f64,
+) -> Option<SmallVec<[usize; 4]>> {
+    let first = (0..points.len()).min_by(|&a, &b| points[a][0].total_cmp(&points[b][0]))?;
+    let mut simplex: SmallVec<[usize; 4]> = SmallVec::from_slice(&[first]);
+    // An orthonormal basis of the directions spanned so far.
+    let mut basis: Vec<Vector<f64, N>> = vec![];
+    for _ in 0..N {
+        let residual = |p: &Vector<f64, N>| {
+            let mut d = p - points[first];
+            for b in &basis {
+                d -= b * b.dot(&d);
+            }
+            d
+        };
+        let next = (0..points.len()).max_by(|&a, &b| {
+            residual(&points[a])
+                .norm()
+                .total_cmp(&residual(&points[b]).norm())
+        })?;
+        let direction = residual(&points[next]);
+        if direction.norm() <= epsilon {
+            return None;
+        }
+        basis.push(direction.normalize());
+        simplex.push(next);
+    }
+    Some(simplex)
+}
+
+// Creates a facet through the vertices, with its normal pointing away from the interior point.
+fn make_facet<const N: usize>(
+    points: &[Vector<f64, N>],
+    vertices: SmallVec<[usize; 4]>,
+    interior: Vector<f64, N>,
+) -> Option<Facet<N>> {
+    let origin = points[vertices[0]];
+    let edges = DMatrix::from_fn(N - 1, N, |r, c| points[vertices[r + 1]][c] - origin[c]);
+    // The generalized cross product of the edges, from the cofactors.
+    let normal = Vector::<f64, N>::from_fn(|i, _| {
+        let minor = edges.clone().remove_column(i).determinant();
+        if i % 2 == 0 {
+            minor
+        } else {
+            -minor
+        }
+    });
+    let mut normal = normal.try_normalize(0.0)?;
+    if normal.dot(&(interior - origin)) > 0.0 {
+        normal = -normal;
+    }
+    Some(Facet {
+        vertices,
+        offset: normal.dot(&origin),
+        normal,
+        outside: vec![],
+        alive: true,
+    })
+}
+
+// Gives each point to the first facet from `first` onwards which it is above.
+fn assign<const N: usize>(
+    points: &[Vector<f64, N>],
+    facets: &mut [Facet<N>],
+    first: usize,
+    indices: impl Iterator<Item = usize>,
+    epsilon: f64,
+) {
+    for i in indices {
+        if let Some(facet) = facets[first..]
+            .iter_mut()
+            .find(|f| f.height(points[i]) > epsilon)
+        {
+            facet.outside.push(i);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Vector2, Vector3};
+    use rand::Rng;
+    use shape::Cuboid;
+
+    use super::*;
+
+    #[test]
+    fn cube_with_interior_points() {
+        let mut rng = Pcg64Mcg::seed_from_u64(3);
+        let mut points = (0..8)
+            .map(|i| Vector3::new((i & 1) as f32, (i >> 1 & 1) as f32, (i >> 2) as f32))
+            .collect::<Vec<_>>();
+        points.extend((0..50).map(|_| Vector3::from_fn(|_, _| rng.gen_range(0.01..0.99))));
+        // Points on the faces and edges aren't vertices either.
+        points.extend([
+            Vector3::new(0.5, 0.5, 0.0),
+            Vector3::new(1.0, 0.3, 0.7),
+            Vector3::new(0.5, 1.0, 1.0),
+        ]);
+        let hull = ConvexHull::new(&points).unwrap();
+        assert_eq!(hull.vertices().len(), 8);
+        assert_eq!(hull.half_spaces().len(), 6);
+        let cube = Cuboid::new(Vector3::repeat(0.5)) + Vector3::repeat(0.5);
+        for _ in 0..100 {
+            let point = Vector3::from_fn(|_, _| rng.gen_range(-1.0..2.0));
+            assert_eq!(hull.contains(point), cube.contains(point));
+            assert!(hull.distance(point) <= cube.distance(point) + 1e-5);
+        }
+    }
+
+    #[test]
+    fn flat_input() {
+        assert_eq!(ConvexHull::<3>::new(&[]).err(), Some(PolytopeError::Flat));
+        assert_eq!(
+            ConvexHull::new(&[Vector2::new(1.0, 2.0); 5]).err(),
+            Some(PolytopeError::Flat)
+        );
+        let collinear = (0..10)
+            .map(|i| Vector2::new(i as f32, 2.0 * i as f32))
+            .collect::<Vec<_>>();
+        assert_eq!(ConvexHull::new(&collinear).err(), Some(PolytopeError::Flat));
+        let coplanar = (0..20)
+            .map(|i| {
+                let (x, y) = ((i % 5) as f32, (i / 5) as f32);
+                Vector3::new(x, y, x - y)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(ConvexHull::new(&coplanar).err(), Some(PolytopeError::Flat));
+    }
+
+    #[test]
+    fn duplicate_and_collinear_vertices() {
+        // A square with repeated corners and points along its edges.
+        let mut points = vec![
+            Vector2::new(0.0, 0.0),
+            Vector2::new(2.0, 0.0),
+            Vector2::new(2.0, 2.0),
+            Vector2::new(0.0, 2.0),
+        ];
+        points.extend(points.clone());
+        points.extend((1..4).map(|i| Vector2::new(i as f32 * 0.5, 0.0)));
+        points.push(Vector2::new(1.0, 1.0));
+        let hull = ConvexHull::new(&points).unwrap();
+        assert_eq!(hull.half_spaces().len(), 4);
+        let mut vertices = hull.vertices().to_vec();
+        vertices.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+        vertices.dedup();
+        assert_eq!(
+            vertices,
+            [
+                Vector2::new(0.0, 0.0),
+                Vector2::new(0.0, 2.0),
+                Vector2::new(2.0, 0.0),
+                Vector2::new(2.0, 2.0)
+            ]
+        );
+    }
+}
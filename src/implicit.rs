@@ -0,0 +1,61 @@
+use super::*;
+
+/// A volume defined by a signed distance function, with the gradient estimated by central differences.
+///
+/// The function should be negative inside and never overestimate the distance to the surface.
+#[derive(Debug, Clone, Copy)]
+pub struct FnVolume<F, const N: usize>
+where
+    F: Fn(Vector<f32, N>) -> f32 + Sync,
+{
+    f: F,
+    min: Vector<f32, N>,
+    max: Vector<f32, N>,
+    epsilon: f32,
+}
+impl<F, const N: usize> FnVolume<F, N>
+where
+    F: Fn(Vector<f32, N>) -> f32 + Sync,
+{
+    /// Creates a volume from a distance function, which must be positive outside of the bounds.
+    pub fn new(f: F, min: Vector<f32, N>, max: Vector<f32, N>) -> Self {
+        let epsilon = (max - min).amax() * 1e-4;
+        Self {
+            f,
+            min,
+            max,
+            epsilon: if epsilon > 0.0 { epsilon } else { 1e-4 },
+        }
+    }
+    /// Sets the step size used for the central differences.
+    /// This defaults to `1e-4` times the largest side of the bounds.
+    pub fn with_epsilon(self, epsilon: f32) -> Self {
+        Self { epsilon, ..self }
+    }
+    pub fn function(&self) -> &F {
+        &self.f
+    }
+}
+
+impl<F, const N: usize> VolumeCore<N> for FnVolume<F, N>
+where
+    F: Fn(Vector<f32, N>) -> f32 + Sync,
+{
+    fn distance(&self, point: Vector<f32, N>) -> f32 {
+        (self.f)(point)
+    }
+    fn gradient(&self, point: Vector<f32, N>) -> Vector<f32, N> {
+        central_difference(&self.f, point, self.epsilon)
+            .try_normalize(1e-12)
+            .unwrap_or(Vector::zeros())
+    }
+    fn contains(&self, point: Vector<f32, N>) -> bool {
+        (self.f)(point) <= 0.0
+    }
+    fn min_bound(&self) -> Vector<f32, N> {
+        self.min
+    }
+    fn max_bound(&self) -> Vector<f32, N> {
+        self.max
+    }
+}
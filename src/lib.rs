@@ -9,10 +9,26 @@ use rand_pcg::Pcg64Mcg;
 use smallvec::SmallVec;
 
 pub mod base;
+pub mod bvh;
+pub mod csg;
 pub mod ext;
+pub mod heightfield;
+pub mod hull;
+pub mod implicit;
+pub mod mesh;
+pub mod morphology;
+pub mod offset;
+pub mod path;
+pub mod periodic;
+pub mod polytope;
+pub mod sdf;
 pub mod shape;
 pub mod solver;
+pub mod svg;
+pub mod sweep;
+pub mod transform;
 pub mod utils;
+pub mod validate;
 use base::*;
 pub use ext::{GridSettings, PackedSettings, ParticleSettings, Volume};
 use solver::*;
@@ -0,0 +1,366 @@
+use std::fmt::{self, Display, Formatter};
+
+use bvh::Bvh;
+use nalgebra::Vector3;
+
+use super::*;
+
+// How far away a cluster of triangles has to be, relative to its radius,
+// before its winding number contribution is approximated as a dipole.
+const WINDING_APPROX_RATIO: f32 = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeshError {
+    Empty,
+    IndexOutOfBounds {
+        triangle: usize,
+    },
+    /// A triangle without area, such as one with a repeated vertex.
+    DegenerateTriangle {
+        triangle: usize,
+    },
+    /// An edge which is only used by one triangle.
+    OpenEdge {
+        edge: (u32, u32),
+    },
+    /// An edge which is used by more than two triangles.
+    NonManifoldEdge {
+        edge: (u32, u32),
+    },
+    /// An edge which is traversed in the same direction by both of its triangles.
+    InconsistentOrientation {
+        edge: (u32, u32),
+    },
+}
+impl Display for MeshError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            MeshError::Empty => write!(f, "mesh has no triangles"),
+            MeshError::IndexOutOfBounds { triangle } => {
+                write!(f, "triangle {} has a vertex index out of bounds", triangle)
+            }
+            MeshError::DegenerateTriangle { triangle } => {
+                write!(f, "triangle {} has no area", triangle)
+            }
+            MeshError::OpenEdge { edge } => {
+                write!(f, "edge {:?} is only used by one triangle", edge)
+            }
+            MeshError::NonManifoldEdge { edge } => {
+                write!(f, "edge {:?} is used by more than two triangles", edge)
+            }
+            MeshError::InconsistentOrientation { edge } => {
+                write!(
+                    f,
+                    "triangles sharing edge {:?} have opposite orientations",
+                    edge
+                )
+            }
+        }
+    }
+}
+impl std::error::Error for MeshError {}
+
+#[derive(Debug, Clone, Copy)]
+struct Cluster {
+    center: Vector3<f32>,
+    // The sum of the area-weighted normals of the triangles in the cluster.
+    normal: Vector3<f32>,
+    radius: f32,
+}
+
+/// A closed, consistently oriented triangle mesh.
+#[derive(Debug, Clone)]
+pub struct TriangleMesh {
+    vertices: Vec<Vector3<f32>>,
+    triangles: Vec<[u32; 3]>,
+    bvh: Bvh<3>,
+    clusters: Vec<Cluster>,
+}
+impl TriangleMesh {
+    /// Creates a mesh, checking that it is closed and manifold, and that no triangle is degenerate.
+    /// Meshes with inward-facing triangles are flipped. Problems are reported for the first triangle
+    /// they affect.
+    pub fn new(vertices: Vec<Vector3<f32>>, triangles: Vec<[u32; 3]>) -> Result<Self, MeshError> {
+        if triangles.is_empty() {
+            return Err(MeshError::Empty);
+        }
+        let mut edges: HashMap<(u32, u32), u32> = HashMap::new();
+        for (i, triangle) in triangles.iter().enumerate() {
+            if triangle.iter().any(|&v| v as usize >= vertices.len()) {
+                return Err(MeshError::IndexOutOfBounds { triangle: i });
+            }
+            let [a, b, c] = triangle.map(|v| vertices[v as usize]);
+            let longest = (b - a).norm().max((c - b).norm()).max((a - c).norm());
+            if (b - a).cross(&(c - a)).norm() <= longest * longest * f32::EPSILON {
+                return Err(MeshError::DegenerateTriangle { triangle: i });
+            }
+            for j in 0..3 {
+                *edges
+                    .entry((triangle[j], triangle[(j + 1) % 3]))
+                    .or_default() += 1;
+            }
+        }
+        // Going through the triangles rather than the map keeps the reported edge the same between runs.
+        for (a, b) in triangles
+            .iter()
+            .flat_map(|t| [(t[0], t[1]), (t[1], t[2]), (t[2], t[0])])
+        {
+            let count = edges[&(a, b)];
+            let reverse = edges.get(&(b, a)).copied().unwrap_or(0);
+            let edge = (a.min(b), a.max(b));
+            if count + reverse > 2 {
+                return Err(MeshError::NonManifoldEdge { edge });
+            } else if count == 2 {
+                return Err(MeshError::InconsistentOrientation { edge });
+            } else if reverse == 0 {
+                return Err(MeshError::OpenEdge { edge });
+            }
+        }
+
+        let mut triangles = triangles;
+        let volume: f32 = triangles
+            .iter()
+            .map(|t| {
+                let [a, b, c] = t.map(|v| vertices[v as usize]);
+                a.dot(&b.cross(&c))
+            })
+            .sum();
+        if volume < 0.0 {
+            for t in &mut triangles {
+                t.swap(1, 2);
+            }
+        }
+
+        let bounds = triangles
+            .iter()
+            .map(|t| {
+                let [a, b, c] = t.map(|v| vertices[v as usize]);
+                (a.inf(&b).inf(&c), a.sup(&b).sup(&c))
+            })
+            .collect::<Vec<_>>();
+        let bvh = Bvh::new(&bounds);
+        let mut mesh = TriangleMesh {
+            vertices,
+            triangles,
+            bvh,
+            clusters: vec![],
+        };
+        mesh.clusters = mesh.build_clusters();
+        Ok(mesh)
+    }
+    pub fn vertices(&self) -> &[Vector3<f32>] {
+        &self.vertices
+    }
+    pub fn triangles(&self) -> &[[u32; 3]] {
+        &self.triangles
+    }
+    fn triangle(&self, index: u32) -> [Vector3<f32>; 3] {
+        self.triangles[index as usize].map(|v| self.vertices[v as usize])
+    }
+    fn build_clusters(&self) -> Vec<Cluster> {
+        let mut clusters = vec![
+            Cluster {
+                center: Vector3::zeros(),
+                normal: Vector3::zeros(),
+                radius: 0.0,
+            };
+            self.bvh.nodes.len()
+        ];
+        let mut areas = vec![0.0; self.bvh.nodes.len()];
+        // Children always come after their parents.
+        for (i, node) in self.bvh.nodes.iter().enumerate().rev() {
+            let (center, normal, area) = if node.is_leaf() {
+                self.bvh.indices[node.start as usize..(node.start + node.count) as usize]
+                    .iter()
+                    .fold(
+                        (Vector3::zeros(), Vector3::zeros(), 0.0),
+                        |(c, n, a), &t| {
+                            let [p, q, r] = self.triangle(t);
+                            let normal = (q - p).cross(&(r - p)) / 2.0;
+                            let area = normal.norm();
+                            (c + (p + q + r) / 3.0 * area, n + normal, a + area)
+                        },
+                    )
+            } else {
+                let (l, r) = (node.start as usize, node.start as usize + 1);
+                (
+                    clusters[l].center * areas[l] + clusters[r].center * areas[r],
+                    clusters[l].normal + clusters[r].normal,
+                    areas[l] + areas[r],
+                )
+            };
+            let center = if area > 0.0 {
+                center / area
+            } else {
+                (node.min + node.max) / 2.0
+            };
+            let radius = (center - node.min)
+                .abs()
+                .sup(&(node.max - center).abs())
+                .norm();
+            clusters[i] = Cluster {
+                center,
+                normal,
+                radius,
+            };
+            areas[i] = area;
+        }
+        clusters
+    }
+
+    /// The generalized winding number of the mesh around the point, which is 1 inside and 0 outside.
+    /// Distant groups of triangles are approximated, following
+    /// [Barill et al. 2018](https://www.dgp.toronto.edu/projects/fast-winding-numbers/).
+    pub fn winding_number(&self, point: Vector3<f32>) -> f32 {
+        let mut total = 0.0;
+        let mut stack: SmallVec<[u32; 32]> = SmallVec::new();
+        stack.push(0);
+        while let Some(i) = stack.pop() {
+            let node = &self.bvh.nodes[i as usize];
+            let cluster = &self.clusters[i as usize];
+            let delta = cluster.center - point;
+            let dist = delta.norm();
+            if dist > cluster.radius * WINDING_APPROX_RATIO {
+                total += cluster.normal.dot(&delta) / (dist * dist * dist);
+            } else if node.is_leaf() {
+                for &t in &self.bvh.indices[node.start as usize..(node.start + node.count) as usize]
+                {
+                    let [a, b, c] = self.triangle(t);
+                    total += solid_angle(a - point, b - point, c - point);
+                }
+            } else {
+                stack.push(node.start);
+                stack.push(node.start + 1);
+            }
+        }
+        total / (4.0 * std::f32::consts::PI)
+    }
+}
+
+// https://en.wikipedia.org/wiki/Solid_angle#Tetrahedron
+fn solid_angle(a: Vector3<f32>, b: Vector3<f32>, c: Vector3<f32>) -> f32 {
+    let (la, lb, lc) = (a.norm(), b.norm(), c.norm());
+    let numerator = a.dot(&b.cross(&c));
+    let denominator = la * lb * lc + a.dot(&b) * lc + a.dot(&c) * lb + b.dot(&c) * la;
+    2.0 * numerator.atan2(denominator)
+}
+
+impl VolumeCore<3> for TriangleMesh {
+    fn nearest_surface_point(&self, point: Vector3<f32>) -> (Vector3<f32>, bool) {
+        let project = |t: u32| {
+            let [a, b, c] = self.triangle(t);
+            project_triangle(a, b, c, point)
+        };
+        let (nearest, _) = self
+            .bvh
+            .nearest(point, |t| (project(t) - point).norm())
+            .unwrap();
+        (project(nearest), self.contains(point))
+    }
+    fn gradient(&self, point: Vector3<f32>) -> Vector3<f32> {
+        surface_gradient(point, self.nearest_surface_point(point))
+    }
+    fn contains(&self, point: Vector3<f32>) -> bool {
+        if point.zip_fold(&self.min_bound(), false, |acc, a, b| acc | (a < b))
+            || point.zip_fold(&self.max_bound(), false, |acc, a, b| acc | (a > b))
+        {
+            return false;
+        }
+        self.winding_number(point) >= 0.5
+    }
+    fn min_bound(&self) -> Vector3<f32> {
+        self.bvh.nodes[0].min
+    }
+    fn max_bound(&self) -> Vector3<f32> {
+        self.bvh.nodes[0].max
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shape::Cuboid;
+
+    use super::*;
+
+    fn cube() -> (Vec<Vector3<f32>>, Vec<[u32; 3]>) {
+        let vertices = (0..8)
+            .map(|i| Vector3::new((i & 1) as f32, (i >> 1 & 1) as f32, (i >> 2) as f32) * 2.0)
+            .collect();
+        let triangles = vec![
+            [0, 2, 1],
+            [1, 2, 3],
+            [4, 5, 6],
+            [5, 7, 6],
+            [0, 1, 4],
+            [1, 5, 4],
+            [2, 6, 3],
+            [3, 6, 7],
+            [0, 4, 2],
+            [2, 4, 6],
+            [1, 3, 5],
+            [3, 7, 5],
+        ];
+        (vertices, triangles)
+    }
+
+    #[test]
+    fn closed_cube() {
+        let (vertices, triangles) = cube();
+        let exact = Cuboid::new(Vector3::repeat(1.0)) + Vector3::repeat(1.0);
+        // Both orientations give the same volume.
+        let inward = triangles.iter().map(|&[a, b, c]| [a, c, b]).collect();
+        for mesh in [
+            TriangleMesh::new(vertices.clone(), triangles).unwrap(),
+            TriangleMesh::new(vertices, inward).unwrap(),
+        ] {
+            let mut rng = Pcg64Mcg::seed_from_u64(4);
+            for _ in 0..500 {
+                let point = Vector3::from_fn(|_, _| rng.gen_range(-1.5..3.5));
+                let inside = exact.contains(point);
+                assert_eq!(mesh.contains(point), inside, "{point}");
+                assert!(
+                    (mesh.distance(point) - exact.distance(point)).abs() < 1e-5,
+                    "{point}"
+                );
+                let winding = mesh.winding_number(point);
+                assert!(
+                    (winding - if inside { 1.0 } else { 0.0 }).abs() < 0.1,
+                    "{point} {winding}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_meshes() {
+        let (vertices, mut triangles) = cube();
+        assert_eq!(
+            TriangleMesh::new(vertices.clone(), vec![]).err(),
+            Some(MeshError::Empty)
+        );
+        let mut flipped = triangles.clone();
+        flipped[3] = [5, 6, 7];
+        assert_eq!(
+            TriangleMesh::new(vertices.clone(), flipped).err(),
+            Some(MeshError::InconsistentOrientation { edge: (5, 6) })
+        );
+        let mut degenerate = triangles.clone();
+        degenerate[5] = [1, 1, 4];
+        assert_eq!(
+            TriangleMesh::new(vertices.clone(), degenerate).err(),
+            Some(MeshError::DegenerateTriangle { triangle: 5 })
+        );
+        let mut extra = triangles.clone();
+        extra.push([0, 2, 1]);
+        assert_eq!(
+            TriangleMesh::new(vertices.clone(), extra).err(),
+            Some(MeshError::NonManifoldEdge { edge: (0, 2) })
+        );
+        // The first edge of the first triangle next to the hole is reported every time.
+        triangles.remove(1);
+        assert_eq!(
+            TriangleMesh::new(vertices, triangles).err(),
+            Some(MeshError::OpenEdge { edge: (1, 2) })
+        );
+    }
+}
@@ -0,0 +1,301 @@
+use super::*;
+
+/// A convex volume, which can be added to other volumes with [`Volume::minkowski_sum`].
+pub trait Convex<const N: usize>: VolumeCore<N> {
+    /// A point of the volume which is furthest along the direction.
+    fn support(&self, direction: Vector<f32, N>) -> Vector<f32, N>;
+    /// The center and radius of a ball inside the volume, which should be as large as is easy to find.
+    fn inscribed_ball(&self) -> (Vector<f32, N>, f32) {
+        (self.support(Vector::zeros()), 0.0)
+    }
+}
+
+// How many times the support point is refined.
+const SUPPORT_ITERATIONS: usize = 4;
+
+/// The points `a + b` for `a` in the volume and `b` in the convex shape.
+///
+/// The distance is found by shifting the point by support points of the shape, and never overestimates
+/// if the distance of the volume is exact. Outside, it is exact where the support point settles on the nearest one,
+/// which it may not near concave parts of the volume. Elsewhere, it falls back to the distance to the volume grown by
+/// a ball containing the shape.
+#[derive(Debug, Clone, Copy)]
+pub struct MinkowskiSum<A, B> {
+    pub a: A,
+    pub b: B,
+}
+impl<A: VolumeCore<N>, B: Convex<N>, const N: usize> VolumeCore<N> for MinkowskiSum<A, B> {
+    fn distance(&self, point: Vector<f32, N>) -> f32 {
+        minkowski_query(&self.a, &self.b, point).0
+    }
+    fn gradient(&self, point: Vector<f32, N>) -> Vector<f32, N> {
+        minkowski_query(&self.a, &self.b, point).1
+    }
+    fn contains(&self, point: Vector<f32, N>) -> bool {
+        minkowski_query(&self.a, &self.b, point).2
+    }
+    fn min_bound(&self) -> Vector<f32, N> {
+        let period = self.a.period();
+        (self.a.min_bound() + self.b.min_bound()).zip_zip_map(
+            &self.a.min_bound(),
+            &period,
+            |x, y, p| if p > 0.0 { y } else { x },
+        )
+    }
+    fn max_bound(&self) -> Vector<f32, N> {
+        let period = self.a.period();
+        (self.a.max_bound() + self.b.max_bound()).zip_zip_map(
+            &self.a.max_bound(),
+            &period,
+            |x, y, p| if p > 0.0 { y } else { x },
+        )
+    }
+    fn period(&self) -> Vector<f32, N> {
+        self.a.period()
+    }
+}
+
+// Returns the distance and gradient, and whether the point is inside.
+fn minkowski_query<const N: usize>(
+    a: &impl VolumeCore<N>,
+    b: &impl Convex<N>,
+    point: Vector<f32, N>,
+) -> (f32, Vector<f32, N>, bool) {
+    // The distance is at most the smallest distance of the point shifted by any point of the shape, with equality
+    // outside. At the best shift, it's the support point along the volume's gradient, so that is iterated.
+    // Growing by the inscribed ball also gives an upper bound, which is better deep inside.
+    let (center, radius) = b.inscribed_ball();
+    let mut best = (
+        a.distance(point - center) - radius,
+        a.gradient(point - center),
+    );
+    // The iteration has settled if a step stops getting closer, and is only exact if it gets as close as any shift.
+    let (min, max) = (b.min_bound(), b.max_bound());
+    let tolerance = (max - min).amax() * 1e-4;
+    let (mut nearest, mut settled) = (f32::INFINITY, false);
+    let mut gradient = a.gradient(point);
+    for _ in 0..=SUPPORT_ITERATIONS {
+        let shifted = point - b.support(gradient);
+        let dist = a.distance(shifted);
+        gradient = a.gradient(shifted);
+        settled = dist >= nearest - tolerance;
+        nearest = nearest.min(dist);
+        if dist < best.0 {
+            best = (dist, gradient);
+        }
+    }
+    let inside = best.0 <= 0.0;
+    let exact = settled && nearest <= best.0 + tolerance;
+    if !inside && !exact {
+        // Every shift is at most this long, so the volume grown by it contains the sum.
+        let reach = min.abs().sup(&max.abs()).norm();
+        best.0 = best.0.min((a.distance(point) - reach).max(0.0));
+    }
+    (best.0, best.1, inside)
+}
+
+/// The volume with the features that a ball of the radius doesn't fit into removed.
+///
+/// Outside, the distance is the volume's, so it is zero within the removed features.
+#[derive(Debug, Clone, Copy)]
+pub struct Opening<V> {
+    pub volume: V,
+    pub radius: f32,
+}
+impl<V: VolumeCore<N>, const N: usize> VolumeCore<N> for Opening<V> {
+    fn distance(&self, point: Vector<f32, N>) -> f32 {
+        open(&self.volume, 1.0, self.radius, point).0
+    }
+    fn gradient(&self, point: Vector<f32, N>) -> Vector<f32, N> {
+        open(&self.volume, 1.0, self.radius, point).1
+    }
+    fn contains(&self, point: Vector<f32, N>) -> bool {
+        open(&self.volume, 1.0, self.radius, point).2
+    }
+    fn min_bound(&self) -> Vector<f32, N> {
+        self.volume.min_bound()
+    }
+    fn max_bound(&self) -> Vector<f32, N> {
+        self.volume.max_bound()
+    }
+    fn period(&self) -> Vector<f32, N> {
+        self.volume.period()
+    }
+}
+
+/// The volume with the gaps that a ball of the radius doesn't fit into filled.
+///
+/// Gaps are only filled between parts of the volume, so this stays within its bounds.
+/// Inside, the distance is the volume's, so it is zero within the filled gaps.
+#[derive(Debug, Clone, Copy)]
+pub struct Closing<V> {
+    pub volume: V,
+    pub radius: f32,
+}
+impl<V: VolumeCore<N>, const N: usize> VolumeCore<N> for Closing<V> {
+    fn distance(&self, point: Vector<f32, N>) -> f32 {
+        -open(&self.volume, -1.0, self.radius, point).0
+    }
+    fn gradient(&self, point: Vector<f32, N>) -> Vector<f32, N> {
+        -open(&self.volume, -1.0, self.radius, point).1
+    }
+    fn contains(&self, point: Vector<f32, N>) -> bool {
+        !open(&self.volume, -1.0, self.radius, point).2
+    }
+    fn min_bound(&self) -> Vector<f32, N> {
+        self.volume.min_bound()
+    }
+    fn max_bound(&self) -> Vector<f32, N> {
+        self.volume.max_bound()
+    }
+    fn period(&self) -> Vector<f32, N> {
+        self.volume.period()
+    }
+}
+
+// The distance and gradient of the opening of the volume, or of its complement if `sign` is negative,
+// along with whether the point is inside it.
+//
+// The opening is the set of points within the radius of the eroded volume, where the distance is at most `-radius`.
+// The nearest eroded point is found by walking down the gradient, which gets stuck in features that are too thin.
+// Since the opening is within the volume, the volume's distance is a lower bound on the opening's.
+fn open<const N: usize>(
+    volume: &impl VolumeCore<N>,
+    sign: f32,
+    radius: f32,
+    point: Vector<f32, N>,
+) -> (f32, Vector<f32, N>, bool) {
+    let field = |x: Vector<f32, N>| sign * volume.distance(x);
+    let dist = field(point);
+    let gradient = volume.gradient(point) * sign;
+    let tolerance = radius * 1e-3;
+
+    let mut center = point;
+    let mut excess = dist + radius;
+    // Points where a ball fits are unchanged.
+    if excess <= tolerance {
+        return (dist, gradient, true);
+    }
+    for _ in 0..64 {
+        if excess <= tolerance {
+            // The ball around the center is part of the opening, so the distance to it is an upper bound.
+            // That is only conservative inside, and outside the lower bound is used.
+            let offset = point - center;
+            let center_dist = offset.norm() - radius;
+            return if center_dist > 0.0 {
+                (dist.max(0.0), gradient, false)
+            } else if center_dist > dist {
+                (center_dist, offset / offset.norm(), true)
+            } else {
+                (dist, gradient, true)
+            };
+        }
+        // The excess is a lower bound on the distance to the eroded volume, so this doesn't step past it.
+        let next = center - volume.gradient(center) * (sign * excess);
+        let next_excess = field(next) + radius;
+        if next_excess >= excess - tolerance {
+            break;
+        }
+        center = next;
+        excess = next_excess;
+    }
+    // No ball fits here, so the point is outside, although the lower bound may be no more than zero.
+    (dist.max(0.0), gradient, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::Vector2;
+    use shape::{Ball, Cuboid};
+
+    use super::*;
+
+    fn grid() -> impl Iterator<Item = Vector2<f32>> {
+        (-30..30).flat_map(|x| {
+            (-30..30).map(move |y| Vector2::new(x as f32 + 0.5, y as f32 + 0.5) * 0.1)
+        })
+    }
+
+    #[test]
+    fn box_plus_box() {
+        let sum = Cuboid::new(Vector2::new(1.0, 0.5))
+            .minkowski_sum(Cuboid::new(Vector2::new(0.25, 0.25)));
+        let exact = Cuboid::new(Vector2::new(1.25, 0.75));
+        for point in grid() {
+            let (dist, expected) = (sum.distance(point), exact.distance(point));
+            assert_eq!(sum.contains(point), exact.contains(point), "{point}");
+            if expected > 0.0 {
+                assert!((dist - expected).abs() < 1e-5, "{point} {dist} {expected}");
+            } else {
+                assert!(
+                    expected - 1e-5 <= dist && dist <= 0.0,
+                    "{point} {dist} {expected}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn l_shape_plus_ball() {
+        let l = Cuboid::new(Vector2::new(1.0, 0.25))
+            .union(Cuboid::new(Vector2::new(0.25, 1.0)) + Vector2::new(-0.75, 0.75));
+        let sum = l.minkowski_sum(Ball::new(0.5));
+        for point in grid() {
+            // Outside, the union's distance is exact, and so is the rounded outline's.
+            let expected = l.distance(point) - 0.5;
+            let dist = sum.distance(point);
+            assert_eq!(sum.contains(point), expected <= 0.0, "{point}");
+            if expected > 0.0 {
+                assert!((dist - expected).abs() < 1e-4, "{point} {dist} {expected}");
+            } else {
+                assert!(
+                    dist <= 0.0 && dist >= expected - 1e-4,
+                    "{point} {dist} {expected}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn opening_removes_slivers() {
+        let body = Cuboid::new(Vector2::new(1.0, 1.0));
+        let sliver = Cuboid::new(Vector2::new(2.0, 0.05));
+        let opened = body.union(sliver).opening(0.2);
+        assert!(opened.contains(Vector2::new(0.0, 0.0)));
+        assert!(opened.contains(Vector2::new(0.9, 0.6)));
+        assert!(!opened.contains(Vector2::new(1.5, 0.0)));
+        assert!(!opened.contains(Vector2::new(-1.9, 0.0)));
+        // The distance stays conservative where the sliver was and around the body.
+        let dist = opened.distance(Vector2::new(1.5, 0.0));
+        assert!((0.0..=0.5).contains(&dist), "{dist}");
+        for (point, expected) in [
+            (Vector2::new(1.01, 0.0), 0.01),
+            (Vector2::new(1.02, 0.0), 0.02),
+            (Vector2::new(1.05, 0.0), 0.05),
+            (Vector2::new(1.1, 0.0), 0.1),
+            (Vector2::new(1.1, 0.04), 0.1),
+            (Vector2::new(1.1, 0.5), 0.1),
+            (Vector2::new(0.95, 0.0), -0.05),
+            (Vector2::new(0.9, 0.03), -0.1),
+            (Vector2::new(0.5, 0.0), -0.5),
+        ] {
+            let dist = opened.distance(point);
+            assert_eq!(opened.contains(point), expected < 0.0, "{point}");
+            assert!(dist * expected >= 0.0, "{point} {dist}");
+            assert!(dist.abs() <= expected.abs() + 1e-4, "{point} {dist}");
+        }
+    }
+
+    #[test]
+    fn closing_fills_gaps() {
+        let left = Cuboid::new(Vector2::new(1.0, 1.0)) - Vector2::new(1.05, 0.0);
+        let right = Cuboid::new(Vector2::new(1.0, 1.0)) + Vector2::new(1.05, 0.0);
+        let closed = left.union(right).closing(0.5);
+        assert!(closed.contains(Vector2::new(0.0, 0.0)));
+        assert!(closed.contains(Vector2::new(0.0, 0.9)));
+        assert!(!closed.contains(Vector2::new(0.0, 1.2)));
+        assert!(!closed.contains(Vector2::new(3.0, 0.0)));
+        let dist = closed.distance(Vector2::new(0.0, 0.0));
+        assert!((-1.0..=0.0).contains(&dist), "{dist}");
+    }
+}
@@ -0,0 +1,335 @@
+use bvh::Bvh;
+use nalgebra::Vector2;
+use path::{ring_tolerance, Segment};
+use shape::{FillRule, Polygon};
+
+use super::*;
+
+/// How the outlines of neighboring edges are connected at a vertex when offsetting a [`Polygon`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Join {
+    /// A circular arc around the vertex.
+    Round,
+    /// The edges are extended until they meet. If that is further from the vertex than the limit times the
+    /// offset distance, a bevel is used instead, as with the SVG `stroke-miterlimit`.
+    Miter(f32),
+    /// A straight line between the ends of the edges.
+    Bevel,
+}
+
+impl Polygon<2> {
+    /// The outline of the points within `distance` of the polygon, or, if it is negative, of the points
+    /// at least `-distance` inside it. Rings which vanish are removed, and rings which overlap are merged.
+    ///
+    /// Straight edges and arcs are offset exactly, while Bézier curves are flattened first.
+    pub fn offset(&self, distance: f32, join: Join) -> Self {
+        let rings = chain(self.boundary());
+        if distance == 0.0 {
+            return from_rings(rings);
+        }
+        let (min, max) = (self.min_bound(), self.max_bound());
+        let tolerance = ((max - min).amax() + distance.abs()) * 1e-4;
+        // The raw outlines overlap themselves around concave corners and where rings get close,
+        // but the extra loops wind the other way, so only the positive parts are kept.
+        let mut raw = Polygon::new().with_fill_rule(FillRule::Positive);
+        for ring in &rings {
+            raw = raw.add_segments(&offset_ring(ring, distance, join, tolerance));
+        }
+        from_rings(chain(raw.boundary()))
+    }
+}
+
+// Connects pieces of the boundary into closed rings.
+fn chain(pieces: Vec<(Segment<2>, f32, f32)>) -> Vec<Vec<Segment<2>>> {
+    let sections = pieces
+        .iter()
+        .map(|&(segment, t0, t1)| segment.section(t0, t1))
+        .collect::<Vec<_>>();
+    let tolerance = ring_tolerance(&sections);
+    // Splitting edges where they touch can leave tiny pieces, which are skipped over.
+    let (pieces, sections): (Vec<_>, Vec<_>) = pieces
+        .into_iter()
+        .zip(sections)
+        .filter(|(_, section)| {
+            let (min, max) = section.bounds();
+            (max - min).amax() > tolerance
+        })
+        .unzip();
+    let tolerance = tolerance * 100.0;
+    let bounds = sections
+        .iter()
+        .map(|s| (s.start(), s.start()))
+        .collect::<Vec<_>>();
+    let bvh = Bvh::new(&bounds);
+    let mut used = vec![false; pieces.len()];
+    let mut rings = vec![];
+    for first in 0..pieces.len() {
+        if used[first] {
+            continue;
+        }
+        used[first] = true;
+        let mut ring = vec![pieces[first]];
+        let start = sections[first].start();
+        let mut end = sections[first].end();
+        loop {
+            let next = bvh.nearest(end, |i| {
+                if used[i as usize] {
+                    f32::INFINITY
+                } else {
+                    (sections[i as usize].start() - end).norm()
+                }
+            });
+            match next {
+                Some((i, dist)) if dist <= tolerance && dist < (start - end).norm() => {
+                    used[i as usize] = true;
+                    ring.push(pieces[i as usize]);
+                    end = sections[i as usize].end();
+                }
+                _ => break,
+            }
+        }
+        rings.push(merge_sections(ring));
+    }
+    rings
+}
+
+// Joins consecutive pieces which are next to each other on the same edge.
+fn merge_sections(ring: Vec<(Segment<2>, f32, f32)>) -> Vec<Segment<2>> {
+    let mut merged: Vec<(Segment<2>, f32, f32)> = vec![];
+    for (segment, t0, t1) in ring {
+        match merged.last_mut() {
+            Some((last, _, end)) if *last == segment && *end == t0 => *end = t1,
+            _ => merged.push((segment, t0, t1)),
+        }
+    }
+    let mut sections = merged
+        .into_iter()
+        .map(|(segment, t0, t1)| segment.section(t0, t1))
+        .collect::<Vec<_>>();
+    // Close the small gaps left by rounding where possible.
+    for i in 0..sections.len() {
+        let previous = sections[(i + sections.len() - 1) % sections.len()].end();
+        if let Segment::Line(a, _) = &mut sections[i] {
+            *a = previous;
+        }
+    }
+    // Lines continuing in the same direction are joined, such as the ends of a miter along the next edge.
+    let tolerance = ring_tolerance(&sections);
+    let continues = |first: &Segment<2>, second: &Segment<2>| match (*first, *second) {
+        (Segment::Line(a, b), Segment::Line(_, c)) => {
+            (c - a).perp(&(b - a)).abs() <= tolerance * (c - a).norm()
+                && (b - a).dot(&(c - b)) > 0.0
+        }
+        _ => false,
+    };
+    let mut joined: Vec<Segment<2>> = vec![];
+    for section in sections {
+        match joined.last_mut() {
+            Some(last) if continues(last, &section) => {
+                *last = Segment::Line(last.start(), section.end())
+            }
+            _ => joined.push(section),
+        }
+    }
+    while joined.len() > 1 && continues(&joined[joined.len() - 1], &joined[0]) {
+        let last = joined.pop().unwrap();
+        joined[0] = Segment::Line(last.start(), joined[0].end());
+    }
+    joined
+}
+
+fn from_rings(rings: Vec<Vec<Segment<2>>>) -> Polygon<2> {
+    let mut polygon = Polygon::new();
+    for ring in rings {
+        polygon = if ring.iter().all(|s| matches!(s, Segment::Line(..))) {
+            polygon.add_polygon(&ring.iter().map(|s| s.start()).collect::<Vec<_>>())
+        } else {
+            polygon.add_segments(&ring)
+        };
+    }
+    polygon
+}
+
+// The edges of the ring moved to the right by `distance`, connected by joins. The result may intersect itself.
+fn offset_ring(ring: &[Segment<2>], distance: f32, join: Join, tolerance: f32) -> Vec<Segment<2>> {
+    let ring = ring
+        .iter()
+        .filter(|segment| {
+            let (min, max) = segment.bounds();
+            (max - min).amax() > 0.0
+        })
+        .collect::<Vec<_>>();
+    let right = |tangent: Vector2<f32>| {
+        let tangent = tangent.normalize();
+        Vector2::new(tangent.y, -tangent.x)
+    };
+    let mut result = vec![];
+    for (i, segment) in ring.iter().enumerate() {
+        match **segment {
+            Segment::Line(a, b) => {
+                let shift = right(b - a) * distance;
+                result.push(Segment::Line(a + shift, b + shift));
+            }
+            Segment::Arc {
+                center,
+                u,
+                v,
+                sweep,
+            } => {
+                // The right side is away from the center if the arc turns left.
+                let radius = u.norm();
+                let signed = if u.perp(&v) > 0.0 {
+                    distance
+                } else {
+                    -distance
+                };
+                let scale = (radius + signed) / radius;
+                if scale <= 0.0 {
+                    // The arc would come back mirrored past its center, so the outline goes through the center
+                    // instead, which stays connected and makes a loop winding the other way.
+                    result.push(Segment::Line(center + u * scale, center));
+                    result.push(Segment::Line(
+                        center,
+                        center + (segment.end() - center) * scale,
+                    ));
+                } else {
+                    result.push(Segment::Arc {
+                        center,
+                        u: u * scale,
+                        v: v * scale,
+                        sweep,
+                    });
+                }
+            }
+            // The offset curve is approximated directly, rather than joining the offsets of a flattened curve.
+            Segment::Quadratic(_) | Segment::Cubic(_) => {
+                let count = segment.flatten(tolerance).len();
+                let mut a = segment.start() + right(tangent(segment, 0.0)) * distance;
+                for j in 1..=count {
+                    let t = j as f32 / count as f32;
+                    let b = segment.point(t) + right(tangent(segment, t)) * distance;
+                    result.push(Segment::Line(a, b));
+                    a = b;
+                }
+            }
+        }
+
+        let next = ring[(i + 1) % ring.len()];
+        let vertex = segment.end();
+        let (t0, t1) = (tangent(segment, 1.0), tangent(next, 0.0));
+        let (n0, n1) = (right(t0), right(t1));
+        let (end, start) = (vertex + n0 * distance, vertex + n1 * distance);
+        if (end - start).norm() <= tolerance * 1e-2 {
+            continue;
+        }
+        let turn = t0.perp(&t1).atan2(t0.dot(&t1));
+        if turn * distance <= 0.0 {
+            // The outlines overlap, and going through the vertex keeps the loop this makes inside the result.
+            result.push(Segment::Line(end, vertex));
+            result.push(Segment::Line(vertex, start));
+            continue;
+        }
+        match join {
+            Join::Round => {
+                let u = end - vertex;
+                let v = Vector2::new(-u.y, u.x) * turn.signum();
+                result.push(Segment::Arc {
+                    center: vertex,
+                    u,
+                    v,
+                    sweep: turn.abs(),
+                });
+            }
+            Join::Miter(limit) if 1.0 / (turn / 2.0).cos() <= limit => {
+                let miter = vertex + (n0 + n1) * distance / (1.0 + n0.dot(&n1));
+                result.push(Segment::Line(end, miter));
+                result.push(Segment::Line(miter, start));
+            }
+            Join::Miter(_) | Join::Bevel => result.push(Segment::Line(end, start)),
+        }
+    }
+    result
+}
+
+// The direction of the segment, which Bézier curves can lack at their ends if control points coincide.
+fn tangent(segment: &Segment<2>, t: f32) -> Vector2<f32> {
+    let derivative = segment.derivative(t);
+    if derivative != Vector2::zeros() {
+        return derivative;
+    }
+    let step = if t < 0.5 { 1e-3 } else { -1e-3 };
+    (segment.point(t + step) - segment.point(t)) * step.signum()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_PI_2;
+
+    use nalgebra::Rotation2;
+    use path::Path;
+
+    use super::*;
+
+    #[test]
+    fn square_corners() {
+        let square = Polygon::new().add_rect(Vector2::repeat(0.5), Vector2::zeros());
+        for (distance, join, count) in [
+            (0.5, Join::Miter(4.0), 4),
+            (-0.25, Join::Miter(4.0), 4),
+            (-0.25, Join::Round, 4),
+            (0.5, Join::Bevel, 8),
+        ] {
+            let offset = square.offset(distance, join);
+            assert_eq!(offset.polygons().len(), 1, "{distance} {join:?}");
+            assert_eq!(offset.polygons()[0].len(), count, "{distance} {join:?}");
+        }
+        let mitered = square.offset(0.5, Join::Miter(4.0));
+        for vertex in &mitered.polygons()[0] {
+            assert!(
+                (vertex.abs() - Vector2::repeat(1.0)).amax() < 1e-5,
+                "{vertex}"
+            );
+        }
+    }
+
+    #[test]
+    fn circle_inset_past_its_center() {
+        let circle = Polygon::new().add_circle(1.0, Vector2::new(2.0, 3.0));
+        for distance in [-1.0, -1.5, -3.0] {
+            let inset = circle.offset(distance, Join::Round);
+            assert!(inset.polygons().is_empty() && inset.paths().is_empty());
+        }
+        let inset = circle.offset(-0.25, Join::Round);
+        assert!(inset.distance(Vector2::new(2.75, 3.0)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn rounded_rectangle_inset_past_its_corners() {
+        // A square with sides of 4 and corners rounded with a radius of 0.5.
+        let mut path = Path::new(Vector2::new(2.0, -1.5));
+        for i in 0..4 {
+            let turn = Rotation2::new(FRAC_PI_2 * i as f32);
+            path = path
+                .line_to(turn * Vector2::new(2.0, 1.5))
+                .arc(turn * Vector2::new(1.5, 1.5), FRAC_PI_2);
+        }
+        let rounded = Polygon::new().add_path(path);
+        for join in [Join::Round, Join::Miter(4.0), Join::Bevel] {
+            // The corners become sharp, rather than being replaced by mirrored arcs.
+            for distance in [1.0, 1.5] {
+                let inset = rounded.offset(-distance, join);
+                for x in -13..13 {
+                    for y in -13..13 {
+                        let point = Vector2::new(x as f32 + 0.5, y as f32 + 0.5) * 0.1;
+                        // The depth inside the square.
+                        let depth = (point.abs() - Vector2::repeat(2.0 - distance)).max();
+                        assert_eq!(inset.contains(point), depth <= 0.0, "{join:?} {point}");
+                        if depth <= 0.0 {
+                            assert!((inset.distance(point) - depth).abs() < 1e-3);
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,481 @@
+use nalgebra::Vector2;
+
+use super::*;
+
+/// A single edge of a [`Polygon`](crate::shape::Polygon) ring, parameterized over `t` in `[0, 1]`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Segment<const N: usize> {
+    Line(Vector<f32, N>, Vector<f32, N>),
+    /// The points `center + u * cos(a) + v * sin(a)` for `a` in `[0, sweep]`.
+    /// `u` and `v` must be orthogonal and of equal length.
+    Arc {
+        center: Vector<f32, N>,
+        u: Vector<f32, N>,
+        v: Vector<f32, N>,
+        sweep: f32,
+    },
+    Quadratic([Vector<f32, N>; 3]),
+    Cubic([Vector<f32, N>; 4]),
+}
+impl<const N: usize> Segment<N> {
+    pub fn start(&self) -> Vector<f32, N> {
+        self.point(0.0)
+    }
+    pub fn end(&self) -> Vector<f32, N> {
+        self.point(1.0)
+    }
+    pub fn point(&self, t: f32) -> Vector<f32, N> {
+        match *self {
+            Segment::Line(a, b) => {
+                if t == 1.0 {
+                    b
+                } else {
+                    a + (b - a) * t
+                }
+            }
+            Segment::Arc {
+                center,
+                u,
+                v,
+                sweep,
+            } => {
+                let (sin, cos) = arc_sin_cos(t * sweep);
+                center + u * cos + v * sin
+            }
+            Segment::Quadratic([p0, p1, p2]) => {
+                let s = 1.0 - t;
+                p0 * (s * s) + p1 * (2.0 * s * t) + p2 * (t * t)
+            }
+            Segment::Cubic([p0, p1, p2, p3]) => {
+                let s = 1.0 - t;
+                p0 * (s * s * s)
+                    + p1 * (3.0 * s * s * t)
+                    + p2 * (3.0 * s * t * t)
+                    + p3 * (t * t * t)
+            }
+        }
+    }
+    pub fn derivative(&self, t: f32) -> Vector<f32, N> {
+        match *self {
+            Segment::Line(a, b) => b - a,
+            Segment::Arc { u, v, sweep, .. } => {
+                let (sin, cos) = (t * sweep).sin_cos();
+                (v * cos - u * sin) * sweep
+            }
+            Segment::Quadratic([p0, p1, p2]) => ((p1 - p0) * (1.0 - t) + (p2 - p1) * t) * 2.0,
+            Segment::Cubic([p0, p1, p2, p3]) => {
+                let s = 1.0 - t;
+                ((p1 - p0) * (s * s) + (p2 - p1) * (2.0 * s * t) + (p3 - p2) * (t * t)) * 3.0
+            }
+        }
+    }
+    fn second_derivative(&self, t: f32) -> Vector<f32, N> {
+        match *self {
+            Segment::Line(..) => Vector::zeros(),
+            Segment::Arc { u, v, sweep, .. } => {
+                let (sin, cos) = (t * sweep).sin_cos();
+                -(u * cos + v * sin) * (sweep * sweep)
+            }
+            Segment::Quadratic([p0, p1, p2]) => (p0 - p1 * 2.0 + p2) * 2.0,
+            Segment::Cubic([p0, p1, p2, p3]) => {
+                ((p0 - p1 * 2.0 + p2) * (1.0 - t) + (p1 - p2 * 2.0 + p3) * t) * 6.0
+            }
+        }
+    }
+
+    /// The parameters in `(0, 1)` where the segment turns around along the given axis, in increasing order.
+    pub fn extrema(&self, axis: usize) -> SmallVec<[f32; 4]> {
+        let mut roots: SmallVec<[f32; 4]> = match *self {
+            Segment::Line(..) => SmallVec::new(),
+            Segment::Arc { u, v, sweep, .. } => {
+                let first = v[axis].atan2(u[axis]).rem_euclid(std::f32::consts::PI);
+                (0..4)
+                    .map(|i| (first + i as f32 * std::f32::consts::PI) / sweep)
+                    .collect()
+            }
+            Segment::Quadratic([p0, p1, p2]) => {
+                let (a, b) = (p1[axis] - p0[axis], p2[axis] - p1[axis]);
+                if a == b {
+                    SmallVec::new()
+                } else {
+                    SmallVec::from_slice(&[a / (a - b)])
+                }
+            }
+            Segment::Cubic([p0, p1, p2, p3]) => {
+                let d0 = p1[axis] - p0[axis];
+                let d1 = p2[axis] - p1[axis];
+                let d2 = p3[axis] - p2[axis];
+                solve_quadratic(d0 - 2.0 * d1 + d2, 2.0 * (d1 - d0), d0)
+                    .into_iter()
+                    .collect()
+            }
+        };
+        roots.retain(|t| *t > 0.0 && *t < 1.0);
+        roots.sort_by(f32::total_cmp);
+        roots
+    }
+    pub fn bounds(&self) -> (Vector<f32, N>, Vector<f32, N>) {
+        let mut min = self.start().inf(&self.end());
+        let mut max = self.start().sup(&self.end());
+        for axis in 0..N {
+            for t in self.extrema(axis) {
+                let p = self.point(t);
+                min = min.inf(&p);
+                max = max.sup(&p);
+            }
+        }
+        (min, max)
+    }
+
+    pub fn nearest_point(&self, point: Vector<f32, N>) -> Vector<f32, N> {
+        match *self {
+            Segment::Line(a, b) => project_line(a, b, point),
+            Segment::Arc {
+                center,
+                u,
+                v,
+                sweep,
+            } => {
+                let w = point - center;
+                let angle = w.dot(&v).atan2(w.dot(&u)).rem_euclid(std::f32::consts::TAU);
+                if angle <= sweep {
+                    self.point(angle / sweep)
+                } else {
+                    let (start, end) = (self.start(), self.end());
+                    if (start - point).norm() <= (end - point).norm() {
+                        start
+                    } else {
+                        end
+                    }
+                }
+            }
+            Segment::Quadratic(_) | Segment::Cubic(_) => {
+                // Refine the local minima of a dense sampling with Newton's method.
+                const SAMPLES: usize = 16;
+                let dist = |t: f32| (self.point(t) - point).norm_squared();
+                let samples: [f32; SAMPLES + 1] =
+                    std::array::from_fn(|i| dist(i as f32 / SAMPLES as f32));
+                let mut best_t = 0.0;
+                let mut best_dist = samples[0];
+                for i in 0..=SAMPLES {
+                    let is_minimum = (i == 0 || samples[i] <= samples[i - 1])
+                        && (i == SAMPLES || samples[i] <= samples[i + 1]);
+                    if !is_minimum {
+                        continue;
+                    }
+                    let mut t = i as f32 / SAMPLES as f32;
+                    for _ in 0..8 {
+                        let delta = self.point(t) - point;
+                        let d1 = self.derivative(t);
+                        let f = delta.dot(&d1);
+                        let df = d1.norm_squared() + delta.dot(&self.second_derivative(t));
+                        if df <= 0.0 {
+                            break;
+                        }
+                        t = (t - f / df).clamp(0.0, 1.0);
+                    }
+                    for t in [t, i as f32 / SAMPLES as f32] {
+                        if dist(t) < best_dist {
+                            best_dist = dist(t);
+                            best_t = t;
+                        }
+                    }
+                }
+                self.point(best_t)
+            }
+        }
+    }
+
+    /// The same segment traversed in the opposite direction.
+    pub fn reverse(&self) -> Self {
+        match *self {
+            Segment::Line(a, b) => Segment::Line(b, a),
+            Segment::Arc {
+                center,
+                u,
+                v,
+                sweep,
+            } => {
+                let (sin, cos) = arc_sin_cos(sweep);
+                Segment::Arc {
+                    center,
+                    u: u * cos + v * sin,
+                    v: u * sin - v * cos,
+                    sweep,
+                }
+            }
+            Segment::Quadratic([p0, p1, p2]) => Segment::Quadratic([p2, p1, p0]),
+            Segment::Cubic([p0, p1, p2, p3]) => Segment::Cubic([p3, p2, p1, p0]),
+        }
+    }
+
+    /// The part of the segment between the parameters `t0` and `t1`, reparameterized over `[0, 1]`.
+    /// It is reversed if `t1 < t0`.
+    pub fn section(&self, t0: f32, t1: f32) -> Self {
+        if t1 < t0 {
+            return self.section(t1, t0).reverse();
+        }
+        match *self {
+            Segment::Line(..) => Segment::Line(self.point(t0), self.point(t1)),
+            Segment::Arc {
+                center,
+                u,
+                v,
+                sweep,
+            } => {
+                let (sin, cos) = arc_sin_cos(t0 * sweep);
+                Segment::Arc {
+                    center,
+                    u: u * cos + v * sin,
+                    v: v * cos - u * sin,
+                    sweep: sweep * (t1 - t0),
+                }
+            }
+            // The control points of a section are the blossom of the curve at its ends.
+            Segment::Quadratic(points) => Segment::Quadratic([
+                blossom(points, [t0, t0]),
+                blossom(points, [t0, t1]),
+                blossom(points, [t1, t1]),
+            ]),
+            Segment::Cubic(points) => Segment::Cubic([
+                blossom(points, [t0, t0, t0]),
+                blossom(points, [t0, t0, t1]),
+                blossom(points, [t0, t1, t1]),
+                blossom(points, [t1, t1, t1]),
+            ]),
+        }
+    }
+
+    /// Approximates the segment with straight lines, returning the points after the start.
+    pub fn flatten(&self, tolerance: f32) -> Vec<Vector<f32, N>> {
+        let count = match *self {
+            Segment::Line(..) => 1,
+            Segment::Arc { u, sweep, .. } => {
+                let radius = u.norm();
+                let step = if tolerance < radius {
+                    2.0 * (1.0 - tolerance / radius).acos()
+                } else {
+                    std::f32::consts::PI
+                };
+                (sweep / step).ceil() as usize
+            }
+            // The error of linear interpolation is bounded by an eighth of the maximum second derivative.
+            Segment::Quadratic(_) | Segment::Cubic(_) => {
+                let curvature = self
+                    .second_derivative(0.0)
+                    .norm()
+                    .max(self.second_derivative(1.0).norm());
+                (curvature / (8.0 * tolerance)).sqrt().ceil() as usize
+            }
+        };
+        let count = count.clamp(1, 1 << 16);
+        (1..=count)
+            .map(|i| self.point(i as f32 / count as f32))
+            .collect()
+    }
+}
+
+impl Segment<2> {
+    /// The signed number of times the segment crosses the ray going from the point towards positive x.
+    /// Upward crossings are positive. Crossings at the endpoints are counted the same as in
+    /// [pnpoly](https://wrfranklin.org/Research/Short_Notes/pnpoly.html), so that rings are counted consistently.
+    pub fn crossings(&self, point: Vector2<f32>) -> i32 {
+        if let Segment::Line(b, a) = *self {
+            // Same as `Polygon::contains` before curved segments were added.
+            // https://web.archive.org/web/20200313050359/https://wrf.ecse.rpi.edu/Research/Short_Notes/pnpoly.html
+            // Also: https://wrfranklin.org/Research/Short_Notes/pnpoly.html
+            /*
+            Copyright (c) 1970-2003, Wm. Randolph Franklin
+
+            Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
+
+                Redistributions of source code must retain the above copyright notice, this list of conditions and the following disclaimers.
+                Redistributions in binary form must reproduce the above copyright notice in the documentation and/or other materials provided with the distribution.
+                The name of W. Randolph Franklin may not be used to endorse or promote products derived from this Software without specific prior written permission.
+
+            THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
+            */
+            return if (a.y > point.y) != (b.y > point.y)
+                && point.x < (b.x - a.x) * (point.y - a.y) / (b.y - a.y) + a.x
+            {
+                if a.y > b.y {
+                    1
+                } else {
+                    -1
+                }
+            } else {
+                0
+            };
+        }
+        let mut total = 0;
+        let mut t0 = 0.0;
+        let mut p0 = self.start();
+        for t1 in self.extrema(1).into_iter().chain([1.0]) {
+            let p1 = self.point(t1);
+            if (p0.y > point.y) != (p1.y > point.y) {
+                // The piece is monotone in y, so the crossing can be found by bisection.
+                let (mut lo, mut hi) = (t0, t1);
+                for _ in 0..32 {
+                    let mid = (lo + hi) / 2.0;
+                    if (self.point(mid).y > point.y) == (p0.y > point.y) {
+                        lo = mid;
+                    } else {
+                        hi = mid;
+                    }
+                }
+                if point.x < self.point((lo + hi) / 2.0).x {
+                    total += if p1.y > p0.y { 1 } else { -1 };
+                }
+            }
+            t0 = t1;
+            p0 = p1;
+        }
+        total
+    }
+}
+
+// The sine and cosine of an angle along an arc, which are exact at whole turns so that circles end where they start.
+fn arc_sin_cos(angle: f32) -> (f32, f32) {
+    if angle % std::f32::consts::TAU == 0.0 {
+        (0.0, 1.0)
+    } else {
+        angle.sin_cos()
+    }
+}
+
+// De Casteljau's algorithm with a different parameter at each level.
+fn blossom<const N: usize, const K: usize, const D: usize>(
+    points: [Vector<f32, N>; K],
+    params: [f32; D],
+) -> Vector<f32, N> {
+    let mut points = points.to_vec();
+    for t in params {
+        points = points.windows(2).map(|w| w[0].lerp(&w[1], t)).collect();
+    }
+    points[0]
+}
+
+/// Returns the real roots of `a x^2 + b x + c`.
+fn solve_quadratic(a: f32, b: f32, c: f32) -> SmallVec<[f32; 2]> {
+    if a.abs() <= 1e-12 {
+        return if b == 0.0 {
+            SmallVec::new()
+        } else {
+            SmallVec::from_slice(&[-c / b])
+        };
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return SmallVec::new();
+    }
+    // https://en.wikipedia.org/wiki/Loss_of_significance#A_better_algorithm
+    let q = -0.5 * (b + b.signum() * discriminant.sqrt());
+    if q == 0.0 {
+        SmallVec::from_slice(&[0.0])
+    } else {
+        SmallVec::from_slice(&[q / a, c / q])
+    }
+}
+
+/// The distance below which points on a ring are considered equal, covering rounding errors in its segments.
+pub fn ring_tolerance<const N: usize>(segments: &[Segment<N>]) -> f32 {
+    let (min, max) = segments.iter().flat_map(|s| [s.start(), s.end()]).fold(
+        (
+            Vector::<f32, N>::repeat(f32::INFINITY),
+            Vector::<f32, N>::repeat(f32::NEG_INFINITY),
+        ),
+        |(min, max), p| (min.inf(&p), max.sup(&p)),
+    );
+    if segments.is_empty() {
+        return 0.0;
+    }
+    (max - min).amax() * 1e-5 + min.abs().sup(&max.abs()).max() * f32::EPSILON * 4.0
+}
+
+/// A builder for a closed ring made of lines and curves.
+#[derive(Debug, Clone)]
+pub struct Path {
+    start: Vector2<f32>,
+    current: Vector2<f32>,
+    segments: Vec<Segment<2>>,
+}
+impl Path {
+    pub fn new(start: Vector2<f32>) -> Self {
+        Self {
+            start,
+            current: start,
+            segments: Vec::new(),
+        }
+    }
+    pub fn current(&self) -> Vector2<f32> {
+        self.current
+    }
+    pub fn push(mut self, segment: Segment<2>) -> Self {
+        self.current = segment.end();
+        self.segments.push(segment);
+        self
+    }
+    pub fn line_to(self, end: Vector2<f32>) -> Self {
+        let start = self.current;
+        self.push(Segment::Line(start, end))
+    }
+    pub fn quad_to(self, control: Vector2<f32>, end: Vector2<f32>) -> Self {
+        let start = self.current;
+        self.push(Segment::Quadratic([start, control, end]))
+    }
+    pub fn cubic_to(
+        self,
+        control_a: Vector2<f32>,
+        control_b: Vector2<f32>,
+        end: Vector2<f32>,
+    ) -> Self {
+        let start = self.current;
+        self.push(Segment::Cubic([start, control_a, control_b, end]))
+    }
+    /// Continues the path around the center by the given angle, counterclockwise if positive.
+    pub fn arc(self, center: Vector2<f32>, angle: f32) -> Self {
+        if angle == 0.0 {
+            return self;
+        }
+        let u = self.current - center;
+        let v = Vector2::new(-u.y, u.x) * angle.signum();
+        self.push(Segment::Arc {
+            center,
+            u,
+            v,
+            sweep: angle.abs(),
+        })
+    }
+    /// Closes the path with a line back to the start, if necessary.
+    /// No line is added if the path only misses the start due to rounding, such as after a full circle.
+    pub fn close(self) -> Vec<Segment<2>> {
+        let start = self.start;
+        let gap = (self.current - start).amax();
+        let path = if gap > ring_tolerance(&self.segments) && !self.segments.is_empty() {
+            self.line_to(start)
+        } else {
+            self
+        };
+        path.segments
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shape::Polygon;
+
+    use super::*;
+
+    #[test]
+    fn full_circle_crossings() {
+        let center = Vector2::new(2.0, 3.0);
+        let circle = Polygon::new().add_circle(1.0, center);
+        // The ray from the center passes through where the circle starts and ends.
+        assert_eq!(circle.winding_number(center), 1);
+        assert!(circle.contains(Vector2::new(2.5, 3.0)));
+        assert!(!circle.contains(Vector2::new(1.5, 4.0)));
+        let arc = circle.paths()[0][0];
+        assert_eq!(arc.end(), arc.start());
+        assert_eq!(arc.reverse().start(), arc.start());
+    }
+}
@@ -0,0 +1,118 @@
+use super::*;
+
+/// A volume repeated infinitely along the axes with a positive period.
+///
+/// Its bounds along those axes are a single tile, starting at `origin`, which sampling and packing wrap around,
+/// so that the resulting points can be tiled seamlessly.
+///
+/// Only the copies which can overlap the tile are checked, so far from the volume the distance is capped at the
+/// smallest period, while the gradient still points away from the nearest copy.
+#[derive(Debug, Clone, Copy)]
+pub struct Periodic<V: VolumeCore<N>, const N: usize> {
+    volume: V,
+    period: Vector<f32, N>,
+    origin: Vector<f32, N>,
+    // How many copies of the volume to check in each direction.
+    reach: Vector<i32, N>,
+}
+impl<V: VolumeCore<N>, const N: usize> Periodic<V, N> {
+    /// Repeats the volume with the given period, where a period of zero means the axis doesn't repeat.
+    /// The tile is centered on the volume.
+    pub fn new(volume: V, period: Vector<f32, N>) -> Self {
+        let center = (volume.min_bound() + volume.max_bound()) / 2.0;
+        let origin = center - period / 2.0;
+        Self::with_origin(volume, period, origin)
+    }
+    pub fn with_origin(volume: V, period: Vector<f32, N>, origin: Vector<f32, N>) -> Self {
+        let (min, max) = (volume.min_bound(), volume.max_bound());
+        let reach = Vector::from_fn(|i, _| {
+            if period[i] > 0.0 {
+                // Copies further away than this are more than a period away from the tile.
+                let overhang = (origin[i] - min[i]).max(max[i] - origin[i] - period[i]);
+                (overhang.max(0.0) / period[i]).ceil() as i32 + 1
+            } else {
+                0
+            }
+        });
+        Self {
+            volume,
+            period,
+            origin,
+            reach,
+        }
+    }
+    pub fn inner(&self) -> &V {
+        &self.volume
+    }
+    pub fn into_inner(self) -> V {
+        self.volume
+    }
+    pub fn origin(&self) -> Vector<f32, N> {
+        self.origin
+    }
+
+    // Calls `f` with the point as seen by each nearby copy of the volume.
+    fn images(&self, point: Vector<f32, N>, mut f: impl FnMut(Vector<f32, N>)) {
+        let point =
+            Vector::from_fn(|i, _| wrap_coordinate(point[i], self.origin[i], self.period[i]));
+        let shape = self.reach.map(|r| 2 * r as u32 + 1);
+        for i in 0..shape.cast::<usize>().product() {
+            let copy = from_linear(i, shape).cast::<i32>() - self.reach;
+            f(point - copy.cast::<f32>().component_mul(&self.period));
+        }
+    }
+    // Returns the distance and gradient.
+    fn query(&self, point: Vector<f32, N>) -> (f32, Vector<f32, N>) {
+        let mut best = (f32::INFINITY, Vector::zeros());
+        self.images(point, |image| {
+            let dist = self.volume.distance(image);
+            if dist < best.0 {
+                best = (dist, image);
+            }
+        });
+        // Copies which weren't checked are at least the smallest period away.
+        let limit = self
+            .period
+            .iter()
+            .filter(|&&p| p > 0.0)
+            .fold(f32::INFINITY, |a, &b| a.min(b));
+        (best.0.min(limit), self.volume.gradient(best.1))
+    }
+}
+
+impl<V: VolumeCore<N>, const N: usize> VolumeCore<N> for Periodic<V, N> {
+    fn distance(&self, point: Vector<f32, N>) -> f32 {
+        self.query(point).0
+    }
+    fn gradient(&self, point: Vector<f32, N>) -> Vector<f32, N> {
+        self.query(point).1
+    }
+    fn contains(&self, point: Vector<f32, N>) -> bool {
+        let mut inside = false;
+        self.images(point, |image| inside |= self.volume.contains(image));
+        inside
+    }
+    fn min_bound(&self) -> Vector<f32, N> {
+        let min = self.volume.min_bound();
+        Vector::from_fn(|i, _| {
+            if self.period[i] > 0.0 {
+                self.origin[i]
+            } else {
+                min[i]
+            }
+        })
+    }
+    fn max_bound(&self) -> Vector<f32, N> {
+        let max = self.volume.max_bound();
+        Vector::from_fn(|i, _| {
+            if self.period[i] > 0.0 {
+                self.origin[i] + self.period[i]
+            } else {
+                max[i]
+            }
+        })
+    }
+    fn period(&self) -> Vector<f32, N> {
+        self.period.map(|p| p.max(0.0))
+    }
+}
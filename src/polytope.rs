@@ -0,0 +1,621 @@
+use std::fmt::{self, Display, Formatter};
+use std::ops::{Add, Sub};
+
+use morphology::Convex;
+use nalgebra::{DMatrix, DVector};
+
+use super::*;
+
+// Unbounded polytopes are detected by clipping them to a box of this size, and checking whether any vertices are on it.
+const UNBOUNDED_EXTENT: f64 = 1e8;
+
+/// The points `x` with `normal · x <= d`.
+///
+/// Since this is unbounded, it should be intersected with a bounded volume before sampling.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HalfSpace<const N: usize> {
+    normal: Vector<f32, N>,
+    d: f32,
+}
+impl<const N: usize> HalfSpace<N> {
+    /// The normal points out of the half-space, and doesn't have to be normalized.
+    pub fn new(normal: Vector<f32, N>, d: f32) -> Self {
+        let norm = normal.norm();
+        assert!(norm > 0.0, "half-space normal must be nonzero");
+        HalfSpace {
+            normal: normal / norm,
+            d: d / norm,
+        }
+    }
+    /// The half-space bounded by the plane going through the point.
+    pub fn through(point: Vector<f32, N>, normal: Vector<f32, N>) -> Self {
+        Self::new(normal, normal.dot(&point))
+    }
+    pub fn normal(&self) -> Vector<f32, N> {
+        self.normal
+    }
+    pub fn d(&self) -> f32 {
+        self.d
+    }
+    pub fn offset(self, offset: Vector<f32, N>) -> Self {
+        HalfSpace {
+            normal: self.normal,
+            d: self.d + self.normal.dot(&offset),
+        }
+    }
+}
+impl<const N: usize> Add<Vector<f32, N>> for HalfSpace<N> {
+    type Output = Self;
+    fn add(self, rhs: Vector<f32, N>) -> Self::Output {
+        self.offset(rhs)
+    }
+}
+impl<const N: usize> Sub<Vector<f32, N>> for HalfSpace<N> {
+    type Output = Self;
+    fn sub(self, rhs: Vector<f32, N>) -> Self::Output {
+        self.offset(-rhs)
+    }
+}
+impl<const N: usize> VolumeCore<N> for HalfSpace<N> {
+    fn distance(&self, point: Vector<f32, N>) -> f32 {
+        self.normal.dot(&point) - self.d
+    }
+    fn gradient(&self, _point: Vector<f32, N>) -> Vector<f32, N> {
+        self.normal
+    }
+    fn contains(&self, point: Vector<f32, N>) -> bool {
+        self.normal.dot(&point) <= self.d
+    }
+    fn min_bound(&self) -> Vector<f32, N> {
+        plane_bounds(self.normal, f32::NEG_INFINITY, self.d).0
+    }
+    fn max_bound(&self) -> Vector<f32, N> {
+        plane_bounds(self.normal, f32::NEG_INFINITY, self.d).1
+    }
+}
+
+/// The points `x` with `low <= normal · x <= high`.
+///
+/// Since this is unbounded, it should be intersected with a bounded volume before sampling.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Slab<const N: usize> {
+    normal: Vector<f32, N>,
+    low: f32,
+    high: f32,
+}
+impl<const N: usize> Slab<N> {
+    /// The normal doesn't have to be normalized.
+    pub fn new(normal: Vector<f32, N>, low: f32, high: f32) -> Self {
+        let norm = normal.norm();
+        assert!(norm > 0.0, "slab normal must be nonzero");
+        Slab {
+            normal: normal / norm,
+            low: low / norm,
+            high: high / norm,
+        }
+    }
+    pub fn normal(&self) -> Vector<f32, N> {
+        self.normal
+    }
+    pub fn interval(&self) -> (f32, f32) {
+        (self.low, self.high)
+    }
+    pub fn offset(self, offset: Vector<f32, N>) -> Self {
+        let shift = self.normal.dot(&offset);
+        Slab {
+            normal: self.normal,
+            low: self.low + shift,
+            high: self.high + shift,
+        }
+    }
+}
+impl<const N: usize> Add<Vector<f32, N>> for Slab<N> {
+    type Output = Self;
+    fn add(self, rhs: Vector<f32, N>) -> Self::Output {
+        self.offset(rhs)
+    }
+}
+impl<const N: usize> Sub<Vector<f32, N>> for Slab<N> {
+    type Output = Self;
+    fn sub(self, rhs: Vector<f32, N>) -> Self::Output {
+        self.offset(-rhs)
+    }
+}
+impl<const N: usize> VolumeCore<N> for Slab<N> {
+    fn distance(&self, point: Vector<f32, N>) -> f32 {
+        let x = self.normal.dot(&point);
+        (self.low - x).max(x - self.high)
+    }
+    fn gradient(&self, point: Vector<f32, N>) -> Vector<f32, N> {
+        let x = self.normal.dot(&point);
+        if self.low - x > x - self.high {
+            -self.normal
+        } else {
+            self.normal
+        }
+    }
+    fn contains(&self, point: Vector<f32, N>) -> bool {
+        let x = self.normal.dot(&point);
+        x >= self.low && x <= self.high
+    }
+    fn min_bound(&self) -> Vector<f32, N> {
+        plane_bounds(self.normal, self.low, self.high).0
+    }
+    fn max_bound(&self) -> Vector<f32, N> {
+        plane_bounds(self.normal, self.low, self.high).1
+    }
+}
+
+// The bounds of `low <= normal · x <= high`, which are only finite if the normal is along an axis.
+fn plane_bounds<const N: usize>(
+    normal: Vector<f32, N>,
+    low: f32,
+    high: f32,
+) -> (Vector<f32, N>, Vector<f32, N>) {
+    let mut min = Vector::repeat(f32::NEG_INFINITY);
+    let mut max = Vector::repeat(f32::INFINITY);
+    let mut axes = (0..N).filter(|&i| normal[i] != 0.0);
+    if let (Some(axis), None) = (axes.next(), axes.next()) {
+        let (a, b) = (low / normal[axis], high / normal[axis]);
+        min[axis] = a.min(b);
+        max[axis] = a.max(b);
+    }
+    (min, max)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolytopeError {
+    /// No point satisfies all of the half-spaces.
+    Empty,
+    /// The half-spaces don't enclose a bounded region.
+    Unbounded,
+    /// The points of a [`ConvexHull`](crate::hull::ConvexHull) or [`Simplex`] lie in a hyperplane,
+    /// so it has no interior.
+    Flat,
+}
+impl Display for PolytopeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PolytopeError::Empty => write!(f, "half-spaces have an empty intersection"),
+            PolytopeError::Unbounded => write!(f, "half-spaces have an unbounded intersection"),
+            PolytopeError::Flat => write!(f, "points don't span every dimension"),
+        }
+    }
+}
+impl std::error::Error for PolytopeError {}
+
+/// A bounded intersection of half-spaces.
+///
+/// The distance is exact: inside, it's the distance to the nearest face plane,
+/// and outside it comes from projecting onto the convex hull of the vertices.
+#[derive(Debug, Clone)]
+pub struct ConvexPolytope<const N: usize> {
+    half_spaces: Vec<HalfSpace<N>>,
+    vertices: Vec<Vector<f32, N>>,
+    min: Vector<f32, N>,
+    max: Vector<f32, N>,
+}
+impl<const N: usize> ConvexPolytope<N> {
+    /// Finds the vertices of the intersection of the half-spaces.
+    /// This checks every combination of `N` half-spaces, so it is only practical for a moderate number of them.
+    pub fn new(half_spaces: &[HalfSpace<N>]) -> Result<Self, PolytopeError> {
+        let mut planes = half_spaces
+            .iter()
+            .map(|h| (h.normal.cast::<f64>(), h.d as f64))
+            .collect::<Vec<_>>();
+        for i in 0..N {
+            let axis = Vector::<f64, N>::from_fn(|j, _| if i == j { 1.0 } else { 0.0 });
+            planes.push((axis, UNBOUNDED_EXTENT));
+            planes.push((-axis, UNBOUNDED_EXTENT));
+        }
+        let vertices = enumerate_vertices(&planes);
+        if vertices.is_empty() {
+            Err(PolytopeError::Empty)
+        } else if vertices.iter().any(|v| v.amax() >= UNBOUNDED_EXTENT / 2.0) {
+            Err(PolytopeError::Unbounded)
+        } else {
+            Ok(Self::from_parts(
+                half_spaces.to_vec(),
+                vertices.iter().map(|v| v.cast::<f32>()).collect(),
+            ))
+        }
+    }
+    /// The intersection of the half-spaces with the box between `min` and `max`, which is always bounded.
+    pub fn clipped(
+        half_spaces: &[HalfSpace<N>],
+        min: Vector<f32, N>,
+        max: Vector<f32, N>,
+    ) -> Result<Self, PolytopeError> {
+        let mut half_spaces = half_spaces.to_vec();
+        for i in 0..N {
+            let axis = Vector::<f32, N>::from_fn(|j, _| if i == j { 1.0 } else { 0.0 });
+            half_spaces.push(HalfSpace::new(axis, max[i]));
+            half_spaces.push(HalfSpace::new(-axis, -min[i]));
+        }
+        Self::new(&half_spaces)
+    }
+    /// Creates a polytope from half-spaces and the vertices of their intersection, which must be consistent.
+    pub(crate) fn from_parts(
+        half_spaces: Vec<HalfSpace<N>>,
+        vertices: Vec<Vector<f32, N>>,
+    ) -> Self {
+        let (min, max) = vertices.iter().fold(
+            (
+                Vector::<f32, N>::repeat(f32::INFINITY),
+                Vector::<f32, N>::repeat(f32::NEG_INFINITY),
+            ),
+            |(min, max), v| (min.inf(v), max.sup(v)),
+        );
+        ConvexPolytope {
+            half_spaces,
+            vertices,
+            min,
+            max,
+        }
+    }
+    pub fn half_spaces(&self) -> &[HalfSpace<N>] {
+        &self.half_spaces
+    }
+    pub fn vertices(&self) -> &[Vector<f32, N>] {
+        &self.vertices
+    }
+    pub fn offset(self, offset: Vector<f32, N>) -> Self {
+        ConvexPolytope {
+            half_spaces: self.half_spaces.iter().map(|h| h.offset(offset)).collect(),
+            vertices: self.vertices.iter().map(|v| v + offset).collect(),
+            min: self.min + offset,
+            max: self.max + offset,
+        }
+    }
+    // Returns the distance and gradient.
+    fn query(&self, point: Vector<f32, N>) -> (f32, Vector<f32, N>) {
+        let (plane_dist, normal) = self
+            .half_spaces
+            .iter()
+            .map(|h| (h.distance(point), h.normal))
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+            .unwrap_or((f32::INFINITY, Vector::zeros()));
+        if plane_dist <= 0.0 {
+            return (plane_dist, normal);
+        }
+        let delta = point - project_hull(&self.vertices, point);
+        let dist = delta.norm();
+        // The plane distance is a lower bound, which can only be larger due to rounding.
+        if dist > plane_dist {
+            (dist, delta / dist)
+        } else {
+            (plane_dist, normal)
+        }
+    }
+}
+impl<const N: usize> Convex<N> for ConvexPolytope<N> {
+    fn support(&self, direction: Vector<f32, N>) -> Vector<f32, N> {
+        *self
+            .vertices
+            .iter()
+            .max_by(|a, b| a.dot(&direction).total_cmp(&b.dot(&direction)))
+            .unwrap()
+    }
+}
+impl<const N: usize> Add<Vector<f32, N>> for ConvexPolytope<N> {
+    type Output = Self;
+    fn add(self, rhs: Vector<f32, N>) -> Self::Output {
+        self.offset(rhs)
+    }
+}
+impl<const N: usize> Sub<Vector<f32, N>> for ConvexPolytope<N> {
+    type Output = Self;
+    fn sub(self, rhs: Vector<f32, N>) -> Self::Output {
+        self.offset(-rhs)
+    }
+}
+impl<const N: usize> VolumeCore<N> for ConvexPolytope<N> {
+    fn distance(&self, point: Vector<f32, N>) -> f32 {
+        self.query(point).0
+    }
+    fn gradient(&self, point: Vector<f32, N>) -> Vector<f32, N> {
+        self.query(point).1
+    }
+    fn contains(&self, point: Vector<f32, N>) -> bool {
+        self.half_spaces.iter().all(|h| h.contains(point))
+    }
+    fn min_bound(&self) -> Vector<f32, N> {
+        self.min
+    }
+    fn max_bound(&self) -> Vector<f32, N> {
+        self.max
+    }
+}
+
+/// The convex hull of `N + 1` points, such as a triangle or tetrahedron.
+#[derive(Debug, Clone)]
+pub struct Simplex<const N: usize> {
+    polytope: ConvexPolytope<N>,
+    // Each barycentric coordinate is `gradient · x + constant`.
+    barycentric: Vec<(Vector<f32, N>, f32)>,
+}
+impl<const N: usize> Simplex<N> {
+    /// Panics if there aren't exactly `N + 1` vertices, and fails if they lie in a hyperplane.
+    pub fn new(vertices: &[Vector<f32, N>]) -> Result<Self, PolytopeError> {
+        assert_eq!(vertices.len(), N + 1, "a simplex needs N + 1 vertices");
+        // The barycentric coordinates solve `sum(λ_i v_i) = x` with `sum(λ_i) = 1`.
+        let matrix = DMatrix::from_fn(N + 1, N + 1, |r, c| {
+            if r < N {
+                vertices[c][r] as f64
+            } else {
+                1.0
+            }
+        });
+        let scale = vertices
+            .iter()
+            .map(|v| (v - vertices[0]).amax())
+            .fold(0.0, f32::max) as f64;
+        let lu = matrix.lu();
+        if lu.determinant().abs() <= 1e-9 * scale.powi(N as i32) {
+            return Err(PolytopeError::Flat);
+        }
+        let inverse = lu.try_inverse().ok_or(PolytopeError::Flat)?;
+        let barycentric = (0..=N)
+            .map(|i| {
+                let gradient = Vector::<f64, N>::from_fn(|j, _| inverse[(i, j)]);
+                (gradient.cast::<f32>(), inverse[(i, N)] as f32)
+            })
+            .collect::<Vec<_>>();
+        // Each face is where the coordinate of the opposite vertex is zero.
+        let half_spaces = barycentric
+            .iter()
+            .map(|&(gradient, constant)| HalfSpace::new(-gradient, constant))
+            .collect();
+        Ok(Simplex {
+            polytope: ConvexPolytope::from_parts(half_spaces, vertices.to_vec()),
+            barycentric,
+        })
+    }
+    pub fn vertices(&self) -> &[Vector<f32, N>] {
+        self.polytope.vertices()
+    }
+    /// The weights of the vertices which average to the point, which are all nonnegative inside.
+    pub fn barycentric(&self, point: Vector<f32, N>) -> SmallVec<[f32; 4]> {
+        self.barycentric
+            .iter()
+            .map(|(gradient, constant)| gradient.dot(&point) + constant)
+            .collect()
+    }
+    /// The point with the given barycentric coordinates.
+    pub fn point(&self, barycentric: &[f32]) -> Vector<f32, N> {
+        self.vertices()
+            .iter()
+            .zip(barycentric)
+            .map(|(v, w)| v * *w)
+            .sum()
+    }
+    /// A uniformly distributed point inside, from barycentric coordinates following a flat Dirichlet distribution.
+    pub fn sample(&self, rng: &mut impl Rng) -> Vector<f32, N> {
+        // Rounding could put the point just outside, in which case another is drawn so the rest stay uniform.
+        loop {
+            // Normalized exponential variables are Dirichlet distributed.
+            let weights = (0..=N)
+                .map(|_| -(1.0 - rng.gen::<f32>()).ln())
+                .collect::<SmallVec<[f32; 4]>>();
+            let total = weights.iter().sum::<f32>();
+            let point = self.point(&weights) / total;
+            if self.contains(point) {
+                return point;
+            }
+        }
+    }
+    /// Uniformly distributed points inside, drawn from a Dirichlet distribution over the vertices.
+    /// A point which rounding puts outside is drawn again, which is rare.
+    pub fn uniform_points(&self, count: usize) -> Vec<Vector<f32, N>> {
+        self.uniform_points_with_rng(count, Pcg64Mcg::from_entropy())
+    }
+    pub fn uniform_points_with_rng(
+        &self,
+        count: usize,
+        mut rng: impl RngCore,
+    ) -> Vec<Vector<f32, N>> {
+        (0..count).map(|_| self.sample(&mut rng)).collect()
+    }
+    pub fn offset(self, offset: Vector<f32, N>) -> Self {
+        Simplex {
+            polytope: self.polytope.offset(offset),
+            barycentric: self
+                .barycentric
+                .iter()
+                .map(|&(gradient, constant)| (gradient, constant - gradient.dot(&offset)))
+                .collect(),
+        }
+    }
+}
+impl<const N: usize> Convex<N> for Simplex<N> {
+    fn support(&self, direction: Vector<f32, N>) -> Vector<f32, N> {
+        self.polytope.support(direction)
+    }
+}
+impl<const N: usize> Add<Vector<f32, N>> for Simplex<N> {
+    type Output = Self;
+    fn add(self, rhs: Vector<f32, N>) -> Self::Output {
+        self.offset(rhs)
+    }
+}
+impl<const N: usize> Sub<Vector<f32, N>> for Simplex<N> {
+    type Output = Self;
+    fn sub(self, rhs: Vector<f32, N>) -> Self::Output {
+        self.offset(-rhs)
+    }
+}
+impl<const N: usize> VolumeCore<N> for Simplex<N> {
+    fn distance(&self, point: Vector<f32, N>) -> f32 {
+        self.polytope.distance(point)
+    }
+    fn gradient(&self, point: Vector<f32, N>) -> Vector<f32, N> {
+        self.polytope.gradient(point)
+    }
+    fn contains(&self, point: Vector<f32, N>) -> bool {
+        self.barycentric(point).iter().all(|&w| w >= 0.0)
+    }
+    fn min_bound(&self) -> Vector<f32, N> {
+        self.polytope.min_bound()
+    }
+    fn max_bound(&self) -> Vector<f32, N> {
+        self.polytope.max_bound()
+    }
+}
+
+// Finds the vertices of `normal · x <= d` by intersecting every combination of `N` planes.
+fn enumerate_vertices<const N: usize>(planes: &[(Vector<f64, N>, f64)]) -> Vec<Vector<f64, N>> {
+    fn visit<const N: usize>(
+        planes: &[(Vector<f64, N>, f64)],
+        chosen: &mut SmallVec<[usize; 4]>,
+        start: usize,
+        vertices: &mut Vec<Vector<f64, N>>,
+    ) {
+        if chosen.len() < N {
+            for i in start..planes.len() {
+                chosen.push(i);
+                visit(planes, chosen, i + 1, vertices);
+                chosen.pop();
+            }
+            return;
+        }
+        // Generic const dimensions don't support decompositions, so use a dynamic matrix.
+        let matrix = DMatrix::from_fn(N, N, |r, c| planes[chosen[r]].0[c]);
+        let lu = matrix.lu();
+        if lu.determinant().abs() <= 1e-9 {
+            return;
+        }
+        let offsets = DVector::from_fn(N, |r, _| planes[chosen[r]].1);
+        let Some(solution) = lu.solve(&offsets) else {
+            return;
+        };
+        let vertex = Vector::<f64, N>::from_fn(|i, _| solution[i]);
+        let tolerance = 1e-9 * (1.0 + vertex.amax());
+        if planes
+            .iter()
+            .any(|(normal, offset)| normal.dot(&vertex) > offset + tolerance)
+        {
+            return;
+        }
+        if !vertices
+            .iter()
+            .any(|v| (v - vertex).amax() <= tolerance * 10.0)
+        {
+            vertices.push(vertex);
+        }
+    }
+    let mut vertices = vec![];
+    visit(planes, &mut SmallVec::new(), 0, &mut vertices);
+    vertices
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::SQRT_2;
+
+    use nalgebra::Vector2;
+
+    use super::*;
+
+    #[test]
+    fn half_spaces_and_slabs() {
+        let half_space = HalfSpace::new(Vector2::new(0.0, 2.0), 2.0);
+        assert_eq!(half_space.normal(), Vector2::y());
+        assert_eq!(half_space.d(), 1.0);
+        assert_eq!(half_space.distance(Vector2::new(3.0, 3.0)), 2.0);
+        assert!(half_space.contains(Vector2::new(5.0, 1.0)));
+        assert!(!half_space.contains(Vector2::new(5.0, 1.1)));
+        assert_eq!(half_space.max_bound(), Vector2::new(f32::INFINITY, 1.0));
+        let moved = half_space + Vector2::new(4.0, 1.0);
+        assert_eq!(
+            moved,
+            HalfSpace::through(Vector2::new(0.0, 2.0), Vector2::y())
+        );
+        let diagonal = HalfSpace::new(Vector2::repeat(1.0), 0.0);
+        assert!((diagonal.distance(Vector2::new(1.0, 1.0)) - SQRT_2).abs() < 1e-6);
+        assert_eq!(diagonal.max_bound(), Vector2::repeat(f32::INFINITY));
+
+        let slab = Slab::new(Vector2::new(3.0, 0.0), -3.0, 6.0);
+        assert_eq!(slab.interval(), (-1.0, 2.0));
+        assert_eq!(slab.distance(Vector2::new(0.5, 7.0)), -1.5);
+        assert_eq!(slab.distance(Vector2::new(3.0, 0.0)), 1.0);
+        assert_eq!(slab.gradient(Vector2::new(3.0, 0.0)), Vector2::x());
+        assert_eq!(slab.distance(Vector2::new(-2.0, 0.0)), 1.0);
+        assert_eq!(slab.gradient(Vector2::new(-2.0, 0.0)), -Vector2::x());
+        assert_eq!(slab.min_bound(), Vector2::new(-1.0, f32::NEG_INFINITY));
+        assert_eq!(slab.max_bound(), Vector2::new(2.0, f32::INFINITY));
+        assert_eq!((slab - Vector2::new(1.0, 5.0)).interval(), (-2.0, 1.0));
+    }
+
+    #[test]
+    #[should_panic(expected = "normal must be nonzero")]
+    fn half_space_without_normal() {
+        HalfSpace::new(Vector2::zeros(), 1.0);
+    }
+
+    #[test]
+    fn triangle_from_half_spaces() {
+        let half_spaces = [
+            HalfSpace::new(-Vector2::x(), 0.0),
+            HalfSpace::new(-Vector2::y(), 0.0),
+            HalfSpace::new(Vector2::repeat(1.0), 1.0),
+        ];
+        let triangle = ConvexPolytope::new(&half_spaces).unwrap();
+        assert_eq!(triangle.vertices().len(), 3);
+        assert_eq!(triangle.min_bound(), Vector2::zeros());
+        assert_eq!(triangle.max_bound(), Vector2::repeat(1.0));
+        for (point, expected) in [
+            (Vector2::new(0.2, 0.3), -0.2),
+            (Vector2::new(2.0, 2.0), 3.0 / SQRT_2),
+            (Vector2::new(-1.0, -1.0), SQRT_2),
+            (Vector2::new(2.0, -1.0), SQRT_2),
+            (Vector2::new(0.5, -2.0), 2.0),
+        ] {
+            let dist = triangle.distance(point);
+            assert!((dist - expected).abs() < 1e-5, "{point}: {dist}");
+            assert_eq!(triangle.contains(point), expected < 0.0);
+        }
+        let moved = triangle.clone() + Vector2::new(1.0, 0.0);
+        assert!((moved.distance(Vector2::new(1.2, 0.3)) + 0.2).abs() < 1e-5);
+
+        let corner = &half_spaces[..2];
+        assert_eq!(
+            ConvexPolytope::new(corner).unwrap_err(),
+            PolytopeError::Unbounded
+        );
+        let clipped = ConvexPolytope::clipped(corner, Vector2::repeat(-1.0), Vector2::repeat(2.0));
+        let clipped = clipped.unwrap();
+        assert_eq!(clipped.min_bound(), Vector2::zeros());
+        assert_eq!(clipped.max_bound(), Vector2::repeat(2.0));
+        let apart = [half_spaces[0], HalfSpace::new(Vector2::x(), -1.0)];
+        assert_eq!(
+            ConvexPolytope::new(&apart).unwrap_err(),
+            PolytopeError::Empty
+        );
+    }
+
+    #[test]
+    fn uniform_simplex_points() {
+        let triangle = Simplex::new(&[
+            Vector2::new(0.0, 0.0),
+            Vector2::new(1.0, 0.0),
+            Vector2::new(0.0, 1.0),
+        ])
+        .unwrap();
+        let points = triangle.uniform_points_with_rng(4000, Pcg64Mcg::seed_from_u64(1));
+        assert!(points.iter().all(|&p| triangle.contains(p)));
+        // The corner within the line through the midpoints of two sides has a quarter of the area.
+        let corner = points.iter().filter(|p| p.x + p.y < 0.5).count() as f32 / 4000.0;
+        assert!((corner - 0.25).abs() < 0.03, "{corner}");
+
+        // Rounding often puts points outside a sliver, which are drawn again rather than moved.
+        let vertices = [
+            Vector2::new(0.0, 0.0),
+            Vector2::new(1.0, 0.1),
+            Vector2::new(3.0, 0.3 + 1e-5),
+        ];
+        let sliver = Simplex::new(&vertices).unwrap();
+        let centroid = vertices.iter().sum::<Vector2<f32>>() / 3.0;
+        let points = sliver.uniform_points_with_rng(10000, Pcg64Mcg::seed_from_u64(2));
+        assert!(points.iter().all(|&p| sliver.contains(p) && p != centroid));
+    }
+}
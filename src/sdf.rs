@@ -0,0 +1,140 @@
+use super::*;
+
+/// A volume given by signed distances sampled on a regular lattice, which are interpolated multilinearly.
+/// Useful for replacing a volume which is expensive to query.
+///
+/// Outside of the lattice, the distance is the larger of the distance to the lattice and the distance at
+/// the nearest lattice point, which doesn't overestimate as long as the surface lies within the lattice.
+#[derive(Debug, Clone)]
+pub struct SdfGrid<const N: usize> {
+    origin: Vector<f32, N>,
+    spacing: f32,
+    distances: Array<f32, N>,
+    min: Vector<f32, N>,
+    max: Vector<f32, N>,
+}
+impl<const N: usize> SdfGrid<N> {
+    /// Creates a grid where `distances[i]` is the distance at `origin + i * spacing`.
+    /// Panics if the lattice has less than two points along any axis.
+    pub fn new(origin: Vector<f32, N>, spacing: f32, distances: Array<f32, N>) -> Self {
+        assert!(
+            distances.shape().iter().all(|&x| x >= 2),
+            "SdfGrid needs at least two samples along each axis"
+        );
+        let max = origin + (distances.shape() - Vector::repeat(1)).cast::<f32>() * spacing;
+        Self {
+            origin,
+            spacing,
+            distances,
+            min: origin,
+            max,
+        }
+    }
+    /// Samples the volume's distance at the given spacing, covering its bounds with a margin of two samples.
+    pub fn bake(volume: &impl VolumeCore<N>, spacing: f32) -> Self {
+        let origin = volume.min_bound() - Vector::repeat(spacing * 2.0);
+        let shape = (volume.max_bound() - origin)
+            .map(|x| (x / spacing).ceil() as u32 + 3)
+            .sup(&Vector::repeat(2));
+        let distances = Array::from_fn(shape, |index| {
+            volume.distance(origin + index.cast::<f32>() * spacing)
+        });
+        Self {
+            min: volume.min_bound(),
+            max: volume.max_bound(),
+            ..Self::new(origin, spacing, distances)
+        }
+    }
+    pub fn origin(&self) -> Vector<f32, N> {
+        self.origin
+    }
+    pub fn spacing(&self) -> f32 {
+        self.spacing
+    }
+    pub fn distances(&self) -> &Array<f32, N> {
+        &self.distances
+    }
+
+    // Returns the distance and the unnormalized gradient.
+    fn interpolate(&self, point: Vector<f32, N>) -> (f32, Vector<f32, N>) {
+        let shape = self.distances.shape();
+        let local = (point - self.origin) / self.spacing;
+        let cell = local.zip_map(&shape, |x, s| (x.floor().max(0.0) as u32).min(s - 2));
+        let fract = local.zip_map(&cell, |x, c| (x - c as f32).clamp(0.0, 1.0));
+        // Whether the point is past the lattice along each axis.
+        let outside = local.zip_map(&shape, |x, s| x < 0.0 || x > (s - 1) as f32);
+
+        let mut value = 0.0;
+        let mut grad = Vector::<f32, N>::zeros();
+        for corner in 0..1_u32 << N {
+            let offset = Vector::<u32, N>::from_fn(|i, _| (corner >> i) & 1);
+            let sample = self.distances[cell + offset];
+            let weights = fract.zip_map(&offset, |t, o| if o == 1 { t } else { 1.0 - t });
+            value += sample * weights.product();
+            for axis in 0..N {
+                let rest: f32 = (0..N).filter(|&i| i != axis).map(|i| weights[i]).product();
+                let sign = if offset[axis] == 1 { 1.0 } else { -1.0 };
+                grad[axis] += sample * rest * sign / self.spacing;
+            }
+        }
+        let clamped = self.origin + (cell.cast::<f32>() + fract) * self.spacing;
+        let excess = point - clamped;
+        if outside.iter().any(|&x| x) {
+            // The point is at least as far from anything within the lattice as from its nearest point,
+            // and as the lattice itself.
+            if excess.norm() >= value {
+                (excess.norm(), excess.normalize())
+            } else {
+                (
+                    value,
+                    grad.zip_map(&outside, |g, o| if o { 0.0 } else { g }),
+                )
+            }
+        } else {
+            (value, grad)
+        }
+    }
+}
+
+impl<const N: usize> VolumeCore<N> for SdfGrid<N> {
+    fn distance(&self, point: Vector<f32, N>) -> f32 {
+        self.interpolate(point).0
+    }
+    fn gradient(&self, point: Vector<f32, N>) -> Vector<f32, N> {
+        self.interpolate(point)
+            .1
+            .try_normalize(1e-6)
+            .unwrap_or(Vector::zeros())
+    }
+    fn contains(&self, point: Vector<f32, N>) -> bool {
+        self.distance(point) <= 0.0
+    }
+    fn min_bound(&self) -> Vector<f32, N> {
+        self.min
+    }
+    fn max_bound(&self) -> Vector<f32, N> {
+        self.max
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use shape::Ball;
+
+    use super::*;
+
+    #[test]
+    fn conservative_outside_the_lattice() {
+        let ball = Ball::<2>::new(1.0);
+        let grid = SdfGrid::bake(&ball, 0.1);
+        for i in 0..64 {
+            let angle = i as f32 * 0.1;
+            for radius in [1.5, 3.0, 10.0, 100.0] {
+                let point = Vector::<f32, 2>::new(angle.cos(), angle.sin()) * radius;
+                let (dist, exact) = (grid.distance(point), ball.distance(point));
+                assert!(dist <= exact + 1e-3, "{point} {dist} {exact}");
+                assert!(dist >= exact * 0.5, "{point} {dist} {exact}");
+            }
+        }
+    }
+}
@@ -1,6 +1,10 @@
 use std::ops::{Add, Sub};
+use std::sync::OnceLock;
 
-use nalgebra::Vector3;
+use bvh::Bvh;
+use morphology::Convex;
+use nalgebra::{Rotation, Rotation2, SMatrix, Vector3};
+use path::{ring_tolerance, Path, Segment};
 
 use super::*;
 
@@ -26,6 +30,23 @@ impl<const N: usize> Cuboid<N> {
         }
     }
 }
+impl<const N: usize> Convex<N> for Cuboid<N> {
+    fn support(&self, direction: Vector<f32, N>) -> Vector<f32, N> {
+        Vector::from_fn(|i, _| {
+            if direction[i] < 0.0 {
+                self.min[i]
+            } else if direction[i] > 0.0 {
+                self.max[i]
+            } else {
+                (self.min[i] + self.max[i]) / 2.0
+            }
+        })
+    }
+    fn inscribed_ball(&self) -> (Vector<f32, N>, f32) {
+        let half_size = (self.max - self.min) / 2.0;
+        (self.min + half_size, half_size.min())
+    }
+}
 impl<const N: usize> Add<Vector<f32, N>> for Cuboid<N> {
     type Output = Self;
     fn add(self, rhs: Vector<f32, N>) -> Self::Output {
@@ -63,6 +84,9 @@ impl<const N: usize> VolumeCore<N> for Cuboid<N> {
 
         (nearest, inside)
     }
+    fn gradient(&self, point: Vector<f32, N>) -> Vector<f32, N> {
+        surface_gradient(point, self.nearest_surface_point(point))
+    }
     fn contains(&self, point: Vector<f32, N>) -> bool {
         point.zip_fold(&self.min, true, |acc, a, b| acc && (a >= b))
             && point.zip_fold(&self.max, true, |acc, a, b| acc && (a <= b))
@@ -97,6 +121,14 @@ impl<const N: usize> Ball<N> {
         }
     }
 }
+impl<const N: usize> Convex<N> for Ball<N> {
+    fn support(&self, direction: Vector<f32, N>) -> Vector<f32, N> {
+        self.center + direction.try_normalize(0.0).unwrap_or_else(Vector::zeros) * self.radius
+    }
+    fn inscribed_ball(&self) -> (Vector<f32, N>, f32) {
+        (self.center, self.radius)
+    }
+}
 impl<const N: usize> Add<Vector<f32, N>> for Ball<N> {
     type Output = Self;
     fn add(self, rhs: Vector<f32, N>) -> Self::Output {
@@ -122,6 +154,9 @@ impl<const N: usize> VolumeCore<N> for Ball<N> {
         };
         (self.center + dir * self.radius, norm <= self.radius)
     }
+    fn gradient(&self, point: Vector<f32, N>) -> Vector<f32, N> {
+        surface_gradient(point, self.nearest_surface_point(point))
+    }
     fn contains(&self, point: Vector<f32, N>) -> bool {
         (point - self.center).norm_squared() <= self.radius * self.radius
     }
@@ -133,12 +168,473 @@ impl<const N: usize> VolumeCore<N> for Ball<N> {
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+pub struct Ellipsoid<const N: usize> {
+    center: Vector<f32, N>,
+    radii: Vector<f32, N>,
+    // Columns are the directions of the axes.
+    rotation: SMatrix<f32, N, N>,
+}
+impl<const N: usize> Ellipsoid<N> {
+    pub fn new(radii: Vector<f32, N>) -> Self {
+        Ellipsoid {
+            center: Vector::zeros(),
+            radii,
+            rotation: SMatrix::identity(),
+        }
+    }
+    pub fn from_center(center: Vector<f32, N>, radii: Vector<f32, N>) -> Self {
+        Ellipsoid {
+            center,
+            ..Self::new(radii)
+        }
+    }
+    pub fn with_rotation(self, rotation: Rotation<f32, N>) -> Self {
+        Ellipsoid {
+            rotation: rotation.into_inner(),
+            ..self
+        }
+    }
+    pub fn offset(self, offset: Vector<f32, N>) -> Self {
+        Ellipsoid {
+            center: self.center + offset,
+            ..self
+        }
+    }
+    /// Finds the nearest point on an axis-aligned ellipsoid at the origin, for a point in the positive orthant.
+    /// See <https://www.geometrictools.com/Documentation/DistancePointEllipseEllipsoid.pdf>.
+    fn nearest_local(&self, point: Vector<f64, N>) -> Vector<f64, N> {
+        let radii = self.radii.cast::<f64>();
+        let active = |i: usize| point[i] > 0.0;
+        let min_radius = (0..N).map(|i| radii[i]).fold(f64::INFINITY, f64::min);
+        let min_active = (0..N)
+            .filter(|&i| active(i))
+            .map(|i| radii[i])
+            .fold(f64::INFINITY, f64::min);
+        let f = |t: f64| {
+            (0..N)
+                .filter(|&i| active(i))
+                .map(|i| (radii[i] * point[i] / (t + radii[i] * radii[i])).powi(2))
+                .sum::<f64>()
+                - 1.0
+        };
+        let threshold = -min_radius * min_radius;
+        if min_active.is_finite() {
+            let mut lo = -min_active * min_active;
+            let mut hi = lo + radii.component_mul(&point).norm();
+            for _ in 0..200 {
+                let mid = (lo + hi) / 2.0;
+                if mid <= lo || mid >= hi {
+                    break;
+                }
+                if f(mid) > 0.0 {
+                    lo = mid;
+                } else {
+                    hi = mid;
+                }
+            }
+            let t = (lo + hi) / 2.0;
+            if t >= threshold {
+                return Vector::from_fn(|i, _| {
+                    if active(i) {
+                        radii[i] * radii[i] * point[i] / (t + radii[i] * radii[i])
+                    } else {
+                        0.0
+                    }
+                });
+            }
+        }
+        // The point is inside, on the hyperplane through the shortest axis, and the nearest point is off of it.
+        let shortest = (0..N)
+            .filter(|&i| !active(i))
+            .min_by(|&i, &j| radii[i].total_cmp(&radii[j]))
+            .unwrap();
+        let mut nearest: Vector<f64, N> = Vector::from_fn(|i, _| {
+            if active(i) {
+                radii[i] * radii[i] * point[i] / (radii[i] * radii[i] + threshold)
+            } else {
+                0.0
+            }
+        });
+        let rest = nearest.component_div(&radii).norm_squared();
+        nearest[shortest] = radii[shortest] * (1.0 - rest).max(0.0).sqrt();
+        nearest
+    }
+    fn local(&self, point: Vector<f32, N>) -> Vector<f32, N> {
+        self.rotation.transpose() * (point - self.center)
+    }
+}
+impl<const N: usize> Add<Vector<f32, N>> for Ellipsoid<N> {
+    type Output = Self;
+    fn add(self, rhs: Vector<f32, N>) -> Self::Output {
+        self.offset(rhs)
+    }
+}
+impl<const N: usize> Sub<Vector<f32, N>> for Ellipsoid<N> {
+    type Output = Self;
+    fn sub(self, rhs: Vector<f32, N>) -> Self::Output {
+        self.offset(-rhs)
+    }
+}
+impl<const N: usize> VolumeCore<N> for Ellipsoid<N> {
+    fn nearest_surface_point(&self, point: Vector<f32, N>) -> (Vector<f32, N>, bool) {
+        let local = self.local(point);
+        let nearest = self
+            .nearest_local(local.abs().cast())
+            .cast::<f32>()
+            .zip_map(&local, |x, s| x.copysign(s));
+        (self.rotation * nearest + self.center, self.contains(point))
+    }
+    fn gradient(&self, point: Vector<f32, N>) -> Vector<f32, N> {
+        let local = self.local(point);
+        let nearest = self.nearest_local(local.abs().cast()).cast::<f32>();
+        let normal = nearest
+            .component_div(&self.radii.component_mul(&self.radii))
+            .zip_map(&local, |x, s| x.copysign(s));
+        self.rotation * normal.normalize()
+    }
+    fn contains(&self, point: Vector<f32, N>) -> bool {
+        self.local(point).component_div(&self.radii).norm_squared() <= 1.0
+    }
+    fn min_bound(&self) -> Vector<f32, N> {
+        self.center - self.extent()
+    }
+    fn max_bound(&self) -> Vector<f32, N> {
+        self.center + self.extent()
+    }
+}
+impl<const N: usize> Ellipsoid<N> {
+    fn extent(&self) -> Vector<f32, N> {
+        Vector::from_fn(|i, _| {
+            (0..N)
+                .map(|j| (self.rotation[(i, j)] * self.radii[j]).powi(2))
+                .sum::<f32>()
+                .sqrt()
+        })
+    }
+}
+
+/// A line segment with a radius.
+#[derive(Debug, Clone, Copy)]
+pub struct Capsule<const N: usize> {
+    a: Vector<f32, N>,
+    b: Vector<f32, N>,
+    radius: f32,
+}
+impl<const N: usize> Capsule<N> {
+    pub fn new(a: Vector<f32, N>, b: Vector<f32, N>, radius: f32) -> Self {
+        Capsule { a, b, radius }
+    }
+    pub fn offset(self, offset: Vector<f32, N>) -> Self {
+        Capsule {
+            a: self.a + offset,
+            b: self.b + offset,
+            radius: self.radius,
+        }
+    }
+}
+impl<const N: usize> Convex<N> for Capsule<N> {
+    fn support(&self, direction: Vector<f32, N>) -> Vector<f32, N> {
+        let along = direction.dot(&(self.b - self.a));
+        let end = if along > 0.0 {
+            self.b
+        } else if along < 0.0 {
+            self.a
+        } else {
+            (self.a + self.b) / 2.0
+        };
+        end + direction.try_normalize(0.0).unwrap_or_else(Vector::zeros) * self.radius
+    }
+    fn inscribed_ball(&self) -> (Vector<f32, N>, f32) {
+        ((self.a + self.b) / 2.0, self.radius)
+    }
+}
+impl<const N: usize> Add<Vector<f32, N>> for Capsule<N> {
+    type Output = Self;
+    fn add(self, rhs: Vector<f32, N>) -> Self::Output {
+        self.offset(rhs)
+    }
+}
+impl<const N: usize> Sub<Vector<f32, N>> for Capsule<N> {
+    type Output = Self;
+    fn sub(self, rhs: Vector<f32, N>) -> Self::Output {
+        self.offset(-rhs)
+    }
+}
+impl<const N: usize> VolumeCore<N> for Capsule<N> {
+    fn distance(&self, point: Vector<f32, N>) -> f32 {
+        distance_to_line(self.a, self.b, point) - self.radius
+    }
+    fn gradient(&self, point: Vector<f32, N>) -> Vector<f32, N> {
+        tube_gradient(self.a, self.b, point)
+    }
+    fn contains(&self, point: Vector<f32, N>) -> bool {
+        self.distance(point) <= 0.0
+    }
+    fn min_bound(&self) -> Vector<f32, N> {
+        self.a.inf(&self.b) - Vector::repeat(self.radius)
+    }
+    fn max_bound(&self) -> Vector<f32, N> {
+        self.a.sup(&self.b) + Vector::repeat(self.radius)
+    }
+}
+
+// The direction away from the nearest point on the segment, or some perpendicular direction if the point is on it.
+fn tube_gradient<const N: usize>(
+    a: Vector<f32, N>,
+    b: Vector<f32, N>,
+    point: Vector<f32, N>,
+) -> Vector<f32, N> {
+    let delta = point - project_line(a, b, point);
+    if let Some(dir) = delta.try_normalize(1e-6) {
+        return dir;
+    }
+    any_perpendicular((b - a).try_normalize(1e-6).unwrap_or(Vector::zeros()))
+}
+
+/// A chain of line segments with a radius.
+#[derive(Debug, Clone)]
+pub struct Polyline<const N: usize> {
+    points: Vec<Vector<f32, N>>,
+    radius: f32,
+    bvh: Bvh<N>,
+}
+impl<const N: usize> Polyline<N> {
+    pub fn new(points: &[Vector<f32, N>], radius: f32) -> Self {
+        assert!(!points.is_empty(), "Polyline must have at least one point");
+        let bounds = if points.len() == 1 {
+            vec![(points[0], points[0])]
+        } else {
+            points
+                .windows(2)
+                .map(|w| (w[0].inf(&w[1]), w[0].sup(&w[1])))
+                .collect::<Vec<_>>()
+        };
+        Polyline {
+            points: points.to_vec(),
+            radius,
+            bvh: Bvh::new(&bounds),
+        }
+    }
+    fn segment(&self, index: u32) -> (Vector<f32, N>, Vector<f32, N>) {
+        let index = index as usize;
+        (
+            self.points[index],
+            self.points[(index + 1).min(self.points.len() - 1)],
+        )
+    }
+    fn nearest_segment(&self, point: Vector<f32, N>) -> ((Vector<f32, N>, Vector<f32, N>), f32) {
+        let (index, dist) = self
+            .bvh
+            .nearest(point, |i| {
+                let (a, b) = self.segment(i);
+                distance_to_line(a, b, point)
+            })
+            .unwrap();
+        (self.segment(index), dist)
+    }
+}
+impl<const N: usize> VolumeCore<N> for Polyline<N> {
+    fn distance(&self, point: Vector<f32, N>) -> f32 {
+        self.nearest_segment(point).1 - self.radius
+    }
+    fn gradient(&self, point: Vector<f32, N>) -> Vector<f32, N> {
+        let ((a, b), _) = self.nearest_segment(point);
+        tube_gradient(a, b, point)
+    }
+    fn contains(&self, point: Vector<f32, N>) -> bool {
+        self.distance(point) <= 0.0
+    }
+    fn min_bound(&self) -> Vector<f32, N> {
+        self.bvh.nodes[0].min - Vector::repeat(self.radius)
+    }
+    fn max_bound(&self) -> Vector<f32, N> {
+        self.bvh.nodes[0].max + Vector::repeat(self.radius)
+    }
+}
+
+/// How the winding numbers of a [`Polygon`]'s rings decide which points are inside it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FillRule {
+    /// Points enclosed by an odd number of rings, regardless of their orientation.
+    #[default]
+    EvenOdd,
+    /// Points with a nonzero winding number, so overlapping rings with the same orientation are merged.
+    NonZero,
+    /// Points with a positive winding number, where counterclockwise rings add and clockwise rings subtract.
+    Positive,
+}
+impl FillRule {
+    pub fn fills(self, winding: i32) -> bool {
+        match self {
+            FillRule::EvenOdd => winding % 2 != 0,
+            FillRule::NonZero => winding != 0,
+            FillRule::Positive => winding > 0,
+        }
+    }
+}
+
 // TODO: Make this work on 3D? Or only use triangles.
 #[derive(Debug, Clone)]
 pub struct Polygon<const N: usize> {
     polygons: Vec<Vec<Vector<f32, N>>>,
+    // Rings containing curved segments.
+    paths: Vec<Vec<Segment<N>>>,
     min: Vector<f32, N>,
     max: Vector<f32, N>,
+    fill_rule: FillRule,
+    // Built on the first query, since the polygon is constructed incrementally.
+    edges: OnceLock<PolygonEdges<N>>,
+    // The parts of the edges between inside and outside, if some edges are interior under the fill rule.
+    surface: OnceLock<PolygonEdges<N>>,
+}
+
+#[derive(Debug, Clone)]
+struct PolygonEdges<const N: usize> {
+    segments: Vec<Segment<N>>,
+    bvh: Bvh<N>,
+}
+impl<const N: usize> PolygonEdges<N> {
+    fn new(polygons: &[Vec<Vector<f32, N>>], paths: &[Vec<Segment<N>>]) -> Self {
+        let mut segments = vec![];
+        for polygon in polygons.iter().filter(|polygon| !polygon.is_empty()) {
+            let mut b = polygon.last().unwrap();
+            for a in polygon {
+                segments.push(Segment::Line(*b, *a));
+                b = a;
+            }
+        }
+        segments.extend(paths.iter().flatten());
+        Self::from_segments(segments)
+    }
+    fn from_segments(segments: Vec<Segment<N>>) -> Self {
+        let bounds = segments.iter().map(|s| s.bounds()).collect::<Vec<_>>();
+        Self {
+            bvh: Bvh::new(&bounds),
+            segments,
+        }
+    }
+}
+impl PolygonEdges<2> {
+    fn winding(&self, point: Vector2<f32>) -> i32 {
+        let mut winding = 0;
+        self.bvh.visit(
+            // Only edges to the right of the point can be crossed. The slack covers rounding in the intersection.
+            |node| {
+                node.min.y <= point.y
+                    && node.max.y >= point.y
+                    && node.max.x + (node.max.x.abs() + node.max.x - node.min.x) * 1e-5 >= point.x
+            },
+            |i| winding += self.segments[i as usize].crossings(point),
+        );
+        winding
+    }
+    // Splits the edges where they cross each other, and returns the parameter intervals of the pieces which have
+    // a filled region on one side and an empty one on the other. Intervals are reversed so that the filled side is on the left.
+    fn boundary(&self, rule: FillRule) -> Vec<(usize, f32, f32)> {
+        let tolerance = ring_tolerance(&self.segments);
+        // Crossings are found between straight approximations of the edges.
+        let mut pieces = vec![];
+        for (i, segment) in self.segments.iter().enumerate() {
+            let mut a = segment.start();
+            let points = segment.flatten(tolerance * 10.0);
+            let count = points.len() as f32;
+            for (j, b) in points.into_iter().enumerate() {
+                pieces.push((i, j as f32 / count, (j + 1) as f32 / count, a, b));
+                a = b;
+            }
+        }
+        let bounds = pieces
+            .iter()
+            .map(|p| (p.3.inf(&p.4), p.3.sup(&p.4)))
+            .collect::<Vec<_>>();
+        let bvh = Bvh::new(&bounds);
+        let mut splits = vec![vec![0.0, 1.0]; self.segments.len()];
+        for (i, &(edge, t0, t1, a, b)) in pieces.iter().enumerate() {
+            let (min, max) = bounds[i];
+            bvh.visit(
+                |node| node.min <= max && node.max >= min,
+                |j| {
+                    let (other, s0, s1, c, d) = pieces[j as usize];
+                    if other <= edge {
+                        return;
+                    }
+                    let (a, b, c, d) = (
+                        a.cast::<f64>(),
+                        b.cast::<f64>(),
+                        c.cast::<f64>(),
+                        d.cast::<f64>(),
+                    );
+                    let (e, f) = (b - a, d - c);
+                    let denominator = e.perp(&f);
+                    if denominator == 0.0 {
+                        return;
+                    }
+                    let s = (c - a).perp(&f) / denominator;
+                    let t = (c - a).perp(&e) / denominator;
+                    if (0.0..=1.0).contains(&s) && (0.0..=1.0).contains(&t) {
+                        let (t, s) = refine_intersection(
+                            &self.segments[edge],
+                            &self.segments[other],
+                            t0 + (t1 - t0) * s as f32,
+                            s0 + (s1 - s0) * t as f32,
+                        );
+                        splits[edge].push(t);
+                        splits[other].push(s);
+                    }
+                },
+            );
+        }
+        let mut boundary = vec![];
+        for (i, mut splits) in splits.into_iter().enumerate() {
+            splits.sort_by(f32::total_cmp);
+            splits.dedup();
+            for t in splits.windows(2) {
+                let section = self.segments[i].section(t[0], t[1]);
+                let (mid, tangent) = (section.point(0.5), section.derivative(0.5));
+                let Some(tangent) = tangent.try_normalize(0.0) else {
+                    continue;
+                };
+                let normal = Vector2::new(-tangent.y, tangent.x) * tolerance;
+                let left = rule.fills(self.winding(mid + normal));
+                if left != rule.fills(self.winding(mid - normal)) {
+                    boundary.push(if left {
+                        (i, t[0], t[1])
+                    } else {
+                        (i, t[1], t[0])
+                    });
+                }
+            }
+        }
+        boundary
+    }
+    fn surface(&self, rule: FillRule) -> Self {
+        let boundary = self.boundary(rule);
+        Self::from_segments(
+            boundary
+                .into_iter()
+                .map(|(i, t0, t1)| self.segments[i].section(t0, t1))
+                .collect(),
+        )
+    }
+}
+// Improves the parameters of an intersection between two segments with Newton's method.
+fn refine_intersection(a: &Segment<2>, b: &Segment<2>, t: f32, s: f32) -> (f32, f32) {
+    if let (Segment::Line(..), Segment::Line(..)) = (a, b) {
+        return (t, s);
+    }
+    let (mut t, mut s) = (t, s);
+    for _ in 0..4 {
+        let delta = a.point(t) - b.point(s);
+        let (da, db) = (a.derivative(t), -b.derivative(s));
+        let determinant = da.perp(&db);
+        if determinant == 0.0 {
+            break;
+        }
+        t = (t - delta.perp(&db) / determinant).clamp(0.0, 1.0);
+        s = (s - da.perp(&delta) / determinant).clamp(0.0, 1.0);
+    }
+    (t, s)
 }
 impl<const N: usize> Default for Polygon<N> {
     fn default() -> Self {
@@ -149,10 +645,24 @@ impl<const N: usize> Polygon<N> {
     pub fn new() -> Self {
         Polygon {
             polygons: Vec::new(),
+            paths: Vec::new(),
             min: Vector::repeat(f32::INFINITY),
             max: Vector::repeat(f32::NEG_INFINITY),
+            fill_rule: FillRule::EvenOdd,
+            edges: OnceLock::new(),
+            surface: OnceLock::new(),
         }
     }
+    pub fn with_fill_rule(self, fill_rule: FillRule) -> Self {
+        Polygon {
+            fill_rule,
+            surface: OnceLock::new(),
+            ..self
+        }
+    }
+    pub fn fill_rule(&self) -> FillRule {
+        self.fill_rule
+    }
     pub fn add_polygon(self, polygon: &[Vector<f32, N>]) -> Self {
         let min = self.min.inf(
             &polygon
@@ -166,7 +676,65 @@ impl<const N: usize> Polygon<N> {
         );
         let mut polygons = self.polygons;
         polygons.push(polygon.to_vec());
-        Polygon { polygons, min, max }
+        Polygon {
+            polygons,
+            min,
+            max,
+            edges: OnceLock::new(),
+            surface: OnceLock::new(),
+            ..self
+        }
+    }
+    /// Adds a ring made of arbitrary segments, which should each start where the previous one ended.
+    pub fn add_segments(self, segments: &[Segment<N>]) -> Self {
+        let (min, max) = segments
+            .iter()
+            .fold((self.min, self.max), |(min, max), segment| {
+                let (a, b) = segment.bounds();
+                (min.inf(&a), max.sup(&b))
+            });
+        let mut paths = self.paths;
+        paths.push(segments.to_vec());
+        Polygon {
+            paths,
+            min,
+            max,
+            edges: OnceLock::new(),
+            surface: OnceLock::new(),
+            ..self
+        }
+    }
+    /// Replaces all curved rings with straight approximations within the given tolerance.
+    pub fn flatten(&self, tolerance: f32) -> Self {
+        let mut polygon = Polygon {
+            paths: Vec::new(),
+            edges: OnceLock::new(),
+            surface: OnceLock::new(),
+            ..self.clone()
+        };
+        for path in self.paths.iter().filter(|path| !path.is_empty()) {
+            let mut ring = path
+                .iter()
+                .flat_map(|segment| segment.flatten(tolerance))
+                .collect::<Vec<_>>();
+            // The last point closes the ring.
+            ring.rotate_right(1);
+            ring[0] = path[0].start();
+            polygon = polygon.add_polygon(&ring);
+        }
+        polygon
+    }
+    /// The rings made only of straight edges.
+    pub fn polygons(&self) -> &[Vec<Vector<f32, N>>] {
+        &self.polygons
+    }
+    /// The rings containing curved segments.
+    pub fn paths(&self) -> &[Vec<Segment<N>>] {
+        &self.paths
+    }
+    fn edges(&self) -> &PolygonEdges<N> {
+        self.edges
+            .get_or_init(|| PolygonEdges::new(&self.polygons, &self.paths))
     }
 }
 impl Polygon<2> {
@@ -178,57 +746,61 @@ impl Polygon<2> {
             center + Vector2::new(-half_size.x, half_size.y),
         ])
     }
+    pub fn add_circle(self, radius: f32, center: Vector2<f32>) -> Self {
+        self.add_path(
+            Path::new(center + Vector2::new(radius, 0.0)).arc(center, std::f32::consts::TAU),
+        )
+    }
+    pub fn add_path(self, path: Path) -> Self {
+        self.add_segments(&path.close())
+    }
+    /// The signed number of times the rings wind around the point, where counterclockwise is positive.
+    pub fn winding_number(&self, point: Vector2<f32>) -> i32 {
+        self.edges().winding(point)
+    }
+    /// The parts of the edges between the inside and the outside, as the edges they belong to and
+    /// their parameter intervals, which are reversed where needed so that the inside is on the left.
+    pub(crate) fn boundary(&self) -> Vec<(Segment<2>, f32, f32)> {
+        let edges = self.edges();
+        edges
+            .boundary(self.fill_rule)
+            .into_iter()
+            .map(|(i, t0, t1)| (edges.segments[i], t0, t1))
+            .collect()
+    }
+    fn surface(&self) -> &PolygonEdges<2> {
+        if self.fill_rule == FillRule::EvenOdd {
+            // Every edge changes the parity.
+            self.edges()
+        } else {
+            self.surface
+                .get_or_init(|| self.edges().surface(self.fill_rule))
+        }
+    }
 }
 impl VolumeCore<2> for Polygon<2> {
     fn nearest_surface_point(&self, point: Vector<f32, 2>) -> (Vector<f32, 2>, bool) {
-        let mut dist = f32::MAX;
-        let mut nearest_point = Vector2::repeat(0.0);
-
-        for polygon in &self.polygons {
-            let mut b = polygon.last().unwrap();
-            for a in polygon {
-                let proj = project_line(*a, *b, point);
-                if (proj - point).norm() <= dist {
-                    dist = (proj - point).norm();
-                    nearest_point = proj;
-                }
-                b = a;
-            }
-        }
+        let edges = self.surface();
+        let nearest_point = edges
+            .bvh
+            .nearest(point, |i| {
+                (edges.segments[i as usize].nearest_point(point) - point).norm()
+            })
+            .map_or(Vector2::repeat(0.0), |(i, _)| {
+                edges.segments[i as usize].nearest_point(point)
+            });
         (nearest_point, self.contains(point))
     }
+    fn gradient(&self, point: Vector<f32, 2>) -> Vector<f32, 2> {
+        surface_gradient(point, self.nearest_surface_point(point))
+    }
     fn contains(&self, point: Vector<f32, 2>) -> bool {
         if point.zip_fold(&self.min, false, |acc, a, b| acc | (a < b))
             || point.zip_fold(&self.max, false, |acc, a, b| acc | (a > b))
         {
             return false;
         }
-        // https://web.archive.org/web/20200313050359/https://wrf.ecse.rpi.edu/Research/Short_Notes/pnpoly.html
-        // Also: https://wrfranklin.org/Research/Short_Notes/pnpoly.html
-        /*
-        Copyright (c) 1970-2003, Wm. Randolph Franklin
-
-        Permission is hereby granted, free of charge, to any person obtaining a copy of this software and associated documentation files (the "Software"), to deal in the Software without restriction, including without limitation the rights to use, copy, modify, merge, publish, distribute, sublicense, and/or sell copies of the Software, and to permit persons to whom the Software is furnished to do so, subject to the following conditions:
-
-            Redistributions of source code must retain the above copyright notice, this list of conditions and the following disclaimers.
-            Redistributions in binary form must reproduce the above copyright notice in the documentation and/or other materials provided with the distribution.
-            The name of W. Randolph Franklin may not be used to endorse or promote products derived from this Software without specific prior written permission.
-
-        THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY, FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM, OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE SOFTWARE.
-        */
-        let mut interior = false;
-        for polygon in &self.polygons {
-            let mut b = polygon.last().unwrap();
-            for a in polygon {
-                if (a.y > point.y) != (b.y > point.y)
-                    && point.x < (b.x - a.x) * (point.y - a.y) / (b.y - a.y) + a.x
-                {
-                    interior = !interior;
-                }
-                b = a;
-            }
-        }
-        interior
+        self.fill_rule.fills(self.winding_number(point))
     }
     fn min_bound(&self) -> Vector<f32, 2> {
         self.min
@@ -276,6 +848,9 @@ impl<V: VolumeCore<2>> VolumeCore<3> for Extrude3<V> {
         };
         (surface, inside)
     }
+    fn gradient(&self, point: Vector<f32, 3>) -> Vector<f32, 3> {
+        surface_gradient(point, self.nearest_surface_point(point))
+    }
     fn contains(&self, point: Vector<f32, 3>) -> bool {
         point.z >= self.interval.0
             && point.z <= self.interval.1
@@ -290,3 +865,718 @@ impl<V: VolumeCore<2>> VolumeCore<3> for Extrude3<V> {
         self.base.max_bound().push(self.interval.1)
     }
 }
+
+/// Bounds on how an [`ExtrudeWarp`] changes over an interval, used to keep the distance conservative.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WarpLimits {
+    pub min_scale: f32,
+    pub max_scale: f32,
+    /// The largest absolute rate of change of the scale along z.
+    pub max_scale_rate: f32,
+    /// The largest absolute rate of change of the twist along z, in radians per unit.
+    pub max_twist_rate: f32,
+}
+
+/// How the cross-section of a [`WarpedExtrude3`] changes along z.
+/// Closures taking the height and returning the scale and twist implement this.
+pub trait ExtrudeWarp: Sync {
+    /// The scale and counterclockwise twist angle of the cross-section at the height.
+    /// The scale must not be negative, and a scale of zero shrinks the cross-section to a point.
+    fn at(&self, z: f32) -> (f32, f32);
+    /// The default estimates the limits by sampling, so it can miss changes faster than the sample spacing.
+    fn limits(&self, interval: (f32, f32)) -> WarpLimits {
+        const SAMPLES: usize = 1024;
+        let step = (interval.1 - interval.0) / SAMPLES as f32;
+        let mut prev = self.at(interval.0);
+        let mut limits = WarpLimits {
+            min_scale: prev.0,
+            max_scale: prev.0,
+            max_scale_rate: 0.0,
+            max_twist_rate: 0.0,
+        };
+        for i in 1..=SAMPLES {
+            let next = self.at(interval.0 + i as f32 * step);
+            limits.min_scale = limits.min_scale.min(next.0);
+            limits.max_scale = limits.max_scale.max(next.0);
+            if step > 0.0 {
+                limits.max_scale_rate = limits.max_scale_rate.max((next.0 - prev.0).abs() / step);
+                limits.max_twist_rate = limits.max_twist_rate.max((next.1 - prev.1).abs() / step);
+            }
+            prev = next;
+        }
+        limits
+    }
+}
+impl<F: Fn(f32) -> (f32, f32) + Sync> ExtrudeWarp for F {
+    fn at(&self, z: f32) -> (f32, f32) {
+        self(z)
+    }
+}
+
+/// Interpolates the scale and twist linearly between two heights, and extrapolates beyond them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LinearWarp {
+    pub heights: (f32, f32),
+    pub scale: (f32, f32),
+    pub twist: (f32, f32),
+}
+impl LinearWarp {
+    fn rates(&self) -> (f32, f32) {
+        let length = self.heights.1 - self.heights.0;
+        if length == 0.0 {
+            (0.0, 0.0)
+        } else {
+            (
+                (self.scale.1 - self.scale.0) / length,
+                (self.twist.1 - self.twist.0) / length,
+            )
+        }
+    }
+}
+impl ExtrudeWarp for LinearWarp {
+    fn at(&self, z: f32) -> (f32, f32) {
+        let (scale_rate, twist_rate) = self.rates();
+        let offset = z - self.heights.0;
+        (
+            self.scale.0 + scale_rate * offset,
+            self.twist.0 + twist_rate * offset,
+        )
+    }
+    fn limits(&self, interval: (f32, f32)) -> WarpLimits {
+        let (scale_rate, twist_rate) = self.rates();
+        let (a, b) = (self.at(interval.0).0, self.at(interval.1).0);
+        WarpLimits {
+            min_scale: a.min(b),
+            max_scale: a.max(b),
+            max_scale_rate: scale_rate.abs(),
+            max_twist_rate: twist_rate.abs(),
+        }
+    }
+}
+
+/// An extrusion whose cross-section is scaled and twisted around the z axis depending on the height,
+/// such as a tapered hopper or an auger screw.
+///
+/// The distance is the distance within each slice, divided by a bound on how fast the warp can change it,
+/// so it is underestimated where the warp changes quickly.
+#[derive(Debug, Clone, Copy)]
+pub struct WarpedExtrude3<V: VolumeCore<2>, W: ExtrudeWarp> {
+    base: V,
+    interval: (f32, f32),
+    warp: W,
+    min: Vector3<f32>,
+    max: Vector3<f32>,
+    lipschitz: f32,
+}
+impl<V: VolumeCore<2>, W: ExtrudeWarp> WarpedExtrude3<V, W> {
+    pub fn new(base: V, interval: (f32, f32), warp: W) -> Self {
+        let limits = warp.limits(interval);
+        assert!(
+            limits.min_scale >= 0.0,
+            "extrusion scale must not be negative"
+        );
+        let (base_min, base_max) = (base.min_bound(), base.max_bound());
+        let base_radius = base_min.abs().sup(&base_max.abs()).norm();
+        let twisted = limits.max_twist_rate > 0.0 || warp.at(interval.0).1 != 0.0;
+        let (min, max) = if twisted {
+            let r = base_radius * limits.max_scale;
+            (Vector2::repeat(-r), Vector2::repeat(r))
+        } else {
+            let (a, b) = (limits.min_scale, limits.max_scale);
+            (
+                (base_min * a).inf(&(base_min * b)),
+                (base_max * a).sup(&(base_max * b)),
+            )
+        };
+        // Bound the derivative of the slice distance along z for points within the bounds. Scaling changes it by
+        // the rate times the distance from the origin to the nearest point in the base, and twisting moves
+        // points by at most the rate times their radius.
+        let radius = min.abs().sup(&max.abs()).norm();
+        let z_rate = limits.max_scale_rate * base_radius + limits.max_twist_rate * radius;
+        Self {
+            base,
+            interval,
+            warp,
+            min: min.push(interval.0),
+            max: max.push(interval.1),
+            lipschitz: (1.0 + z_rate * z_rate).sqrt(),
+        }
+    }
+    pub fn base(&self) -> &V {
+        &self.base
+    }
+    pub fn interval(&self) -> (f32, f32) {
+        self.interval
+    }
+    pub fn warp(&self) -> &W {
+        &self.warp
+    }
+    /// Maps a point to the cross-section, returning the point in the base along with the scale and twist.
+    pub fn to_base(&self, point: Vector3<f32>) -> (Vector2<f32>, f32, f32) {
+        let (scale, twist) = self.warp.at(point.z);
+        let local = Rotation2::new(-twist) * point.xy() / scale;
+        (local, scale, twist)
+    }
+    // Returns the distance and gradient.
+    fn query(&self, point: Vector3<f32>) -> (f32, Vector3<f32>) {
+        // The bound on the rate of change only holds within the bounds.
+        let outside = point - point.zip_zip_map(&self.min, &self.max, |x, a, b| x.clamp(a, b));
+        let outside_dist = outside.norm();
+        if outside_dist > 0.0 {
+            return (outside_dist, outside / outside_dist);
+        }
+        let (local, scale, twist) = self.to_base(point);
+        let (slice_dist, grad) = if scale > 0.0 {
+            let dist = self.base.distance(local);
+            let local_grad = self.base.gradient(local);
+
+            const H: f32 = 1e-3;
+            let (below, above) = (self.warp.at(point.z - H), self.warp.at(point.z + H));
+            let scale_rate = (above.0 - below.0) / (2.0 * H);
+            let twist_rate = (above.1 - below.1) / (2.0 * H);
+            let local_rate =
+                -Vector2::new(-local.y, local.x) * twist_rate - local * (scale_rate / scale);
+            let grad_xy = Rotation2::new(twist) * local_grad;
+            let grad = Vector3::new(
+                grad_xy.x,
+                grad_xy.y,
+                scale_rate * dist + scale * local_grad.dot(&local_rate),
+            );
+            (
+                scale * dist,
+                grad.try_normalize(1e-6).unwrap_or(grad_xy.push(0.0)),
+            )
+        } else {
+            // The cross-section has shrunk to the origin, like at the tip of a cone.
+            let grad_xy = point.xy().try_normalize(1e-6).unwrap_or(Vector2::x());
+            (point.xy().norm(), grad_xy.push(0.0))
+        };
+        let slice_dist = slice_dist / self.lipschitz;
+
+        let (below_dist, above_dist) = (self.interval.0 - point.z, point.z - self.interval.1);
+        let interval_dist = below_dist.max(above_dist);
+        if interval_dist > slice_dist {
+            let normal = if above_dist > below_dist {
+                Vector3::z()
+            } else {
+                -Vector3::z()
+            };
+            (interval_dist, normal)
+        } else {
+            (slice_dist, grad)
+        }
+    }
+}
+impl<V: VolumeCore<2>, W: ExtrudeWarp> VolumeCore<3> for WarpedExtrude3<V, W> {
+    fn distance(&self, point: Vector3<f32>) -> f32 {
+        self.query(point).0
+    }
+    fn gradient(&self, point: Vector3<f32>) -> Vector3<f32> {
+        self.query(point).1
+    }
+    fn contains(&self, point: Vector3<f32>) -> bool {
+        if point.z < self.interval.0 || point.z > self.interval.1 {
+            return false;
+        }
+        let (local, scale, _) = self.to_base(point);
+        scale > 0.0 && self.base.contains(local)
+    }
+    fn min_bound(&self) -> Vector3<f32> {
+        self.min
+    }
+    fn max_bound(&self) -> Vector3<f32> {
+        self.max
+    }
+}
+
+/// A solid of revolution, made by sweeping a profile in the xz half-plane (given as x and y of the base) around the z axis.
+/// The sweep starts at the positive x axis and goes counterclockwise by `angle`.
+///
+/// Parts of the profile's boundary lying on the axis are treated as surface, so the distance may be underestimated
+/// near the axis. Passing a cross-section which is symmetric about the axis avoids this.
+#[derive(Debug, Clone, Copy)]
+pub struct Revolve3<V: VolumeCore<2>> {
+    pub base: V,
+    pub angle: f32,
+}
+impl<V: VolumeCore<2>> Revolve3<V> {
+    fn is_full(&self) -> bool {
+        self.angle >= std::f32::consts::TAU
+    }
+    // Returns the distance and gradient.
+    fn query(&self, point: Vector3<f32>) -> (f32, Vector3<f32>) {
+        let radius = point.xy().norm();
+        let radial = if radius <= 1e-6 {
+            Vector3::x()
+        } else {
+            Vector3::new(point.x, point.y, 0.0) / radius
+        };
+        let from_profile = |grad: Vector2<f32>| radial * grad.x + Vector3::z() * grad.y;
+        let profile_point = Vector2::new(radius, point.z);
+        if self.is_full() {
+            return (
+                self.base.distance(profile_point),
+                from_profile(self.base.gradient(profile_point)),
+            );
+        }
+        let angle = point.y.atan2(point.x).rem_euclid(std::f32::consts::TAU);
+        let cap_normals = [
+            Vector3::new(0.0, -1.0, 0.0),
+            Vector3::new(-self.angle.sin(), self.angle.cos(), 0.0),
+        ];
+        if angle <= self.angle {
+            let dist = self.base.distance(profile_point);
+            let mut best = (dist, from_profile(self.base.gradient(profile_point)));
+            if dist <= 0.0 {
+                for (offset, normal) in [angle, self.angle - angle].into_iter().zip(cap_normals) {
+                    let (cap_dist, grad) = if offset < std::f32::consts::FRAC_PI_2 {
+                        (radius * offset.sin(), normal)
+                    } else {
+                        (radius, -radial)
+                    };
+                    if -cap_dist > best.0 {
+                        best = (-cap_dist, grad);
+                    }
+                }
+            }
+            best
+        } else {
+            // The nearest point is on one of the caps.
+            let mut best = (f32::INFINITY, Vector3::zeros());
+            let cap_radials = [
+                Vector3::x(),
+                Vector3::new(self.angle.cos(), self.angle.sin(), 0.0),
+            ];
+            for ((offset, normal), cap_radial) in
+                [std::f32::consts::TAU - angle, angle - self.angle]
+                    .into_iter()
+                    .zip(cap_normals)
+                    .zip(cap_radials)
+            {
+                let foot = Vector2::new(radius * offset.cos(), point.z);
+                let height = radius * offset.sin();
+                let (in_plane, in_plane_grad) = if self.base.contains(foot) {
+                    (0.0, Vector2::zeros())
+                } else {
+                    (self.base.distance(foot), self.base.gradient(foot))
+                };
+                let dist = (in_plane * in_plane + height * height).sqrt();
+                if dist < best.0 {
+                    let grad = (cap_radial * in_plane_grad.x + Vector3::z() * in_plane_grad.y)
+                        * in_plane
+                        + normal * height;
+                    best = (dist, grad.try_normalize(1e-6).unwrap_or(normal));
+                }
+            }
+            best
+        }
+    }
+}
+impl<V: VolumeCore<2>> VolumeCore<3> for Revolve3<V> {
+    fn distance(&self, point: Vector3<f32>) -> f32 {
+        self.query(point).0
+    }
+    fn gradient(&self, point: Vector3<f32>) -> Vector3<f32> {
+        self.query(point).1
+    }
+    fn contains(&self, point: Vector3<f32>) -> bool {
+        let angle = point.y.atan2(point.x).rem_euclid(std::f32::consts::TAU);
+        (self.is_full() || angle <= self.angle)
+            && self.base.contains(Vector2::new(point.xy().norm(), point.z))
+    }
+    fn min_bound(&self) -> Vector3<f32> {
+        self.bounds().0
+    }
+    fn max_bound(&self) -> Vector3<f32> {
+        self.bounds().1
+    }
+}
+impl<V: VolumeCore<2>> Revolve3<V> {
+    fn bounds(&self) -> (Vector3<f32>, Vector3<f32>) {
+        let (min, max) = (self.base.min_bound(), self.base.max_bound());
+        let outer = min.x.abs().max(max.x.abs());
+        let inner = min.x.max(0.0);
+        let mut low = Vector2::repeat(f32::INFINITY);
+        let mut high = Vector2::repeat(f32::NEG_INFINITY);
+        // The extreme points of the swept annulus sector are at its ends or along the axes.
+        let angles = (0..4)
+            .map(|i| i as f32 * std::f32::consts::FRAC_PI_2)
+            .filter(|&a| self.is_full() || a <= self.angle)
+            .chain((!self.is_full()).then_some(self.angle));
+        for angle in angles {
+            let dir = Vector2::new(angle.cos(), angle.sin());
+            for r in [inner, outer] {
+                low = low.inf(&(dir * r));
+                high = high.sup(&(dir * r));
+            }
+        }
+        (low.push(min.y), high.push(max.y))
+    }
+}
+
+// Splits a point into its distance from the axis and its height along it.
+fn to_axial(
+    base: Vector3<f32>,
+    axis: Vector3<f32>,
+    point: Vector3<f32>,
+) -> (Vector2<f32>, Vector3<f32>) {
+    let delta = point - base;
+    let height = delta.dot(&axis);
+    let radial = delta - axis * height;
+    let radius = radial.norm();
+    let dir = if radius <= 1e-6 {
+        any_perpendicular(axis)
+    } else {
+        radial / radius
+    };
+    (Vector2::new(radius, height), dir)
+}
+fn from_axial(
+    base: Vector3<f32>,
+    axis: Vector3<f32>,
+    dir: Vector3<f32>,
+    point: Vector2<f32>,
+) -> Vector3<f32> {
+    base + dir * point.x + axis * point.y
+}
+// The extent of the bounding box of a circle with the given normal.
+fn circle_extent(normal: Vector3<f32>, radius: f32) -> Vector3<f32> {
+    normal.map(|x| radius * (1.0 - x * x).max(0.0).sqrt())
+}
+
+/// A truncated cone, or a cylinder if both radii are the same.
+#[derive(Debug, Clone, Copy)]
+pub struct Cone {
+    base: Vector3<f32>,
+    axis: Vector3<f32>,
+    height: f32,
+    start_radius: f32,
+    end_radius: f32,
+}
+impl Cone {
+    /// Creates a cone with its axis going from `start` to `end`, which must be different. Either radius may be zero.
+    pub fn new(start: Vector3<f32>, end: Vector3<f32>, start_radius: f32, end_radius: f32) -> Self {
+        assert!(start != end, "Cone axis must have a nonzero length");
+        let height = (end - start).norm();
+        Cone {
+            base: start,
+            axis: (end - start) / height,
+            height,
+            start_radius,
+            end_radius,
+        }
+    }
+    pub fn offset(self, offset: Vector3<f32>) -> Self {
+        Cone {
+            base: self.base + offset,
+            ..self
+        }
+    }
+    // The outline in axial coordinates, without the edge along the axis.
+    fn profile(&self) -> [Vector2<f32>; 4] {
+        [
+            Vector2::new(0.0, 0.0),
+            Vector2::new(self.start_radius, 0.0),
+            Vector2::new(self.end_radius, self.height),
+            Vector2::new(0.0, self.height),
+        ]
+    }
+    fn nearest_axial(&self, point: Vector2<f32>) -> (Vector2<f32>, Vector2<f32>, bool) {
+        let profile = self.profile();
+        let mut nearest = profile[0];
+        let mut normal = Vector2::zeros();
+        for i in 0..3 {
+            let (a, b) = (profile[i], profile[i + 1]);
+            let proj = project_line(a, b, point);
+            if i == 0 || (proj - point).norm() < (nearest - point).norm() {
+                nearest = proj;
+                // The profile is counterclockwise, so this points outwards.
+                normal = Vector2::new(b.y - a.y, a.x - b.x)
+                    .try_normalize(1e-6)
+                    .unwrap_or(Vector2::zeros());
+            }
+        }
+        let t = point.y / self.height;
+        let inside = (0.0..=1.0).contains(&t)
+            && point.x <= self.start_radius + (self.end_radius - self.start_radius) * t;
+        (nearest, normal, inside)
+    }
+}
+impl Add<Vector3<f32>> for Cone {
+    type Output = Self;
+    fn add(self, rhs: Vector3<f32>) -> Self::Output {
+        self.offset(rhs)
+    }
+}
+impl Sub<Vector3<f32>> for Cone {
+    type Output = Self;
+    fn sub(self, rhs: Vector3<f32>) -> Self::Output {
+        self.offset(-rhs)
+    }
+}
+impl VolumeCore<3> for Cone {
+    fn nearest_surface_point(&self, point: Vector3<f32>) -> (Vector3<f32>, bool) {
+        let (axial, dir) = to_axial(self.base, self.axis, point);
+        let (nearest, _, inside) = self.nearest_axial(axial);
+        (from_axial(self.base, self.axis, dir, nearest), inside)
+    }
+    fn gradient(&self, point: Vector3<f32>) -> Vector3<f32> {
+        let (axial, dir) = to_axial(self.base, self.axis, point);
+        let (nearest, normal, inside) = self.nearest_axial(axial);
+        let grad =
+            (axial - nearest)
+                .try_normalize(1e-6)
+                .map_or(normal, |g| if inside { -g } else { g });
+        dir * grad.x + self.axis * grad.y
+    }
+    fn contains(&self, point: Vector3<f32>) -> bool {
+        self.nearest_axial(to_axial(self.base, self.axis, point).0)
+            .2
+    }
+    fn min_bound(&self) -> Vector3<f32> {
+        let end = self.base + self.axis * self.height;
+        (self.base - circle_extent(self.axis, self.start_radius))
+            .inf(&(end - circle_extent(self.axis, self.end_radius)))
+    }
+    fn max_bound(&self) -> Vector3<f32> {
+        let end = self.base + self.axis * self.height;
+        (self.base + circle_extent(self.axis, self.start_radius))
+            .sup(&(end + circle_extent(self.axis, self.end_radius)))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Cylinder(Cone);
+impl Cylinder {
+    pub fn new(start: Vector3<f32>, end: Vector3<f32>, radius: f32) -> Self {
+        Cylinder(Cone::new(start, end, radius, radius))
+    }
+    pub fn offset(self, offset: Vector3<f32>) -> Self {
+        Cylinder(self.0.offset(offset))
+    }
+}
+impl Add<Vector3<f32>> for Cylinder {
+    type Output = Self;
+    fn add(self, rhs: Vector3<f32>) -> Self::Output {
+        self.offset(rhs)
+    }
+}
+impl Sub<Vector3<f32>> for Cylinder {
+    type Output = Self;
+    fn sub(self, rhs: Vector3<f32>) -> Self::Output {
+        self.offset(-rhs)
+    }
+}
+impl VolumeCore<3> for Cylinder {
+    fn nearest_surface_point(&self, point: Vector3<f32>) -> (Vector3<f32>, bool) {
+        self.0.nearest_surface_point(point)
+    }
+    fn gradient(&self, point: Vector3<f32>) -> Vector3<f32> {
+        self.0.gradient(point)
+    }
+    fn contains(&self, point: Vector3<f32>) -> bool {
+        self.0.contains(point)
+    }
+    fn min_bound(&self) -> Vector3<f32> {
+        self.0.min_bound()
+    }
+    fn max_bound(&self) -> Vector3<f32> {
+        self.0.max_bound()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Torus {
+    center: Vector3<f32>,
+    axis: Vector3<f32>,
+    major_radius: f32,
+    minor_radius: f32,
+}
+impl Torus {
+    pub fn new(
+        center: Vector3<f32>,
+        axis: Vector3<f32>,
+        major_radius: f32,
+        minor_radius: f32,
+    ) -> Self {
+        Torus {
+            center,
+            axis: axis.normalize(),
+            major_radius,
+            minor_radius,
+        }
+    }
+    pub fn offset(self, offset: Vector3<f32>) -> Self {
+        Torus {
+            center: self.center + offset,
+            ..self
+        }
+    }
+    // The direction from the tube's center circle to the point, in axial coordinates.
+    fn tube_direction(&self, axial: Vector2<f32>) -> Vector2<f32> {
+        (axial - Vector2::new(self.major_radius, 0.0))
+            .try_normalize(1e-6)
+            .unwrap_or(Vector2::new(1.0, 0.0))
+    }
+}
+impl Add<Vector3<f32>> for Torus {
+    type Output = Self;
+    fn add(self, rhs: Vector3<f32>) -> Self::Output {
+        self.offset(rhs)
+    }
+}
+impl Sub<Vector3<f32>> for Torus {
+    type Output = Self;
+    fn sub(self, rhs: Vector3<f32>) -> Self::Output {
+        self.offset(-rhs)
+    }
+}
+impl VolumeCore<3> for Torus {
+    fn nearest_surface_point(&self, point: Vector3<f32>) -> (Vector3<f32>, bool) {
+        let (axial, dir) = to_axial(self.center, self.axis, point);
+        let nearest =
+            Vector2::new(self.major_radius, 0.0) + self.tube_direction(axial) * self.minor_radius;
+        (
+            from_axial(self.center, self.axis, dir, nearest),
+            self.contains(point),
+        )
+    }
+    fn distance(&self, point: Vector3<f32>) -> f32 {
+        let (axial, _) = to_axial(self.center, self.axis, point);
+        (axial - Vector2::new(self.major_radius, 0.0)).norm() - self.minor_radius
+    }
+    fn gradient(&self, point: Vector3<f32>) -> Vector3<f32> {
+        let (axial, dir) = to_axial(self.center, self.axis, point);
+        let grad = self.tube_direction(axial);
+        dir * grad.x + self.axis * grad.y
+    }
+    fn contains(&self, point: Vector3<f32>) -> bool {
+        self.distance(point) <= 0.0
+    }
+    fn min_bound(&self) -> Vector3<f32> {
+        self.center
+            - circle_extent(self.axis, self.major_radius)
+            - Vector3::repeat(self.minor_radius)
+    }
+    fn max_bound(&self) -> Vector3<f32> {
+        self.center
+            + circle_extent(self.axis, self.major_radius)
+            + Vector3::repeat(self.minor_radius)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ext::Volume2d;
+
+    use super::*;
+
+    #[test]
+    #[should_panic(expected = "nonzero length")]
+    fn cone_without_length() {
+        Cylinder::new(Vector3::zeros(), Vector3::zeros(), 1.0);
+    }
+
+    #[test]
+    fn overlapping_squares() {
+        let square = |min: Vector2<f32>| {
+            [
+                min,
+                min + Vector2::new(2.0, 0.0),
+                min + Vector2::repeat(2.0),
+                min + Vector2::new(0.0, 2.0),
+            ]
+        };
+        let a = square(Vector2::zeros());
+        let b = square(Vector2::repeat(1.0));
+        let mut reversed = b;
+        reversed.reverse();
+        let points = [
+            Vector2::new(0.5, 0.5),
+            Vector2::new(1.5, 1.5),
+            Vector2::new(1.5, 1.2),
+            Vector2::new(2.5, 2.5),
+        ];
+        // Every edge is surface, so the overlap is a hole.
+        let alternating = [-0.5, 0.5, 0.2, -0.5];
+        // The squares are merged, so the nearest surface in the overlap is a corner where the squares cross.
+        let merged = [-0.5, -0.5f32.sqrt(), -0.29f32.sqrt(), -0.5];
+        // The clockwise square is cut out of the other one.
+        let subtracted = [-0.5, 0.5, 0.2, 2.5f32.sqrt()];
+        for (b, fill_rule, expected) in [
+            (b, FillRule::EvenOdd, alternating),
+            (b, FillRule::NonZero, merged),
+            (b, FillRule::Positive, merged),
+            (reversed, FillRule::EvenOdd, alternating),
+            (reversed, FillRule::NonZero, alternating),
+            (reversed, FillRule::Positive, subtracted),
+        ] {
+            let polygon = Polygon::new()
+                .add_polygon(&a)
+                .add_polygon(&b)
+                .with_fill_rule(fill_rule);
+            for (point, expected) in points.into_iter().zip(expected) {
+                let dist = polygon.distance(point);
+                assert!(
+                    (dist - expected).abs() < 1e-5,
+                    "{fill_rule:?} {point}: {dist}"
+                );
+                assert_eq!(
+                    polygon.contains(point),
+                    expected < 0.0,
+                    "{fill_rule:?} {point}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn tapered_to_a_point() {
+        let cone = Ball::new(1.0).extrude_tapered(0.0, 1.0, 1.0, 0.0);
+        // The distance to the cone, from the radius and height.
+        let exact = |r: f32, z: f32| {
+            if z >= 0.0 && r + z <= 1.0 {
+                -z.min((1.0 - z - r) / 2f32.sqrt())
+            } else {
+                let to_segment = |a: Vector2<f32>, b: Vector2<f32>| {
+                    let p = Vector2::new(r, z);
+                    let t = ((p - a).dot(&(b - a)) / (b - a).norm_squared()).clamp(0.0, 1.0);
+                    (a + (b - a) * t - p).norm()
+                };
+                to_segment(Vector2::zeros(), Vector2::x())
+                    .min(to_segment(Vector2::x(), Vector2::y()))
+            }
+        };
+        let tip = Vector3::z();
+        assert_eq!(cone.distance(tip), 0.0);
+        assert!(!cone.contains(tip));
+        assert!(cone.contains(Vector3::new(0.0, 0.0, 0.5)));
+        // The slope of the sides only makes the distance somewhat smaller.
+        let side = Vector3::new(1.0, 0.0, 0.5);
+        assert!(cone.distance(side) > 0.8 * exact(1.0, 0.5));
+
+        let mut rng = Pcg64Mcg::seed_from_u64(3);
+        for _ in 0..2000 {
+            let point = Vector3::new(
+                rng.gen_range(-1.5..1.5),
+                rng.gen_range(-1.5..1.5),
+                rng.gen_range(-0.5..1.5),
+            );
+            let dist = cone.distance(point);
+            let expected = exact(point.xy().norm(), point.z);
+            assert!(dist.is_finite(), "{point}");
+            assert_eq!(cone.contains(point), expected < 0.0, "{point}");
+            assert!(
+                dist.abs() <= expected.abs() + 1e-5,
+                "{point}: {dist} {expected}"
+            );
+            assert!(dist * expected >= 0.0, "{point}: {dist} {expected}");
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "must not be negative")]
+    fn tapered_past_a_point() {
+        Ball::new(1.0).extrude_tapered(0.0, 1.0, 1.0, -1.0);
+    }
+}
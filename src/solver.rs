@@ -21,6 +21,15 @@ impl<V: VolumeCore<N>, const N: usize> Solver<V, N> {
             boundary_penetration: f32::INFINITY,
         }
     }
+    /// Moves the points into the tile along periodic axes of the volume.
+    pub fn wrap_points(&mut self) {
+        if self.volume.period().iter().all(|&p| p <= 0.0) {
+            return;
+        }
+        for point in &mut self.points {
+            *point = self.volume.wrap(*point);
+        }
+    }
     pub fn update_grid(&mut self) {
         self.point_grid.clear();
         for (i, point) in self.points.iter().enumerate() {
@@ -28,16 +37,43 @@ impl<V: VolumeCore<N>, const N: usize> Solver<V, N> {
             self.point_grid.entry(cell).or_default().push(i as u32);
         }
     }
+    /// Calls `f` with the points in the cells around the point.
+    /// Along periodic axes, points across the edge of the tile are given shifted by the period, to be next to the point.
+    /// This assumes that the period is at least twice the cell size.
     pub fn neighbors(&self, point_index: usize, mut f: impl FnMut(u32, Vector<f32, N>)) {
         let point = self.points[point_index];
-        let cell = self.volume.containing_cell(point);
-        for i in 0..3_usize.pow(N as u32) {
-            let offset =
-                from_linear(i, Vector::<_, N>::repeat(3)).cast::<i32>() - Vector::repeat(1);
-            if let Some(adj) = self.point_grid.get(&(cell + offset)) {
-                for &adj in adj {
-                    if adj != point_index as u32 {
-                        f(adj, self.points[adj as usize]);
+        let period = self.volume.period();
+        let mut images: SmallVec<[Vector<f32, N>; 8]> = SmallVec::new();
+        images.push(Vector::zeros());
+        if period.iter().any(|&p| p > 0.0) {
+            let (min, max) = (self.volume.min_bound(), self.volume.max_bound());
+            let reach = self.volume.cell_size * 2.0;
+            for i in 0..N {
+                if period[i] <= 0.0 {
+                    continue;
+                }
+                for j in 0..images.len() {
+                    for sign in [-1.0, 1.0] {
+                        let shifted = point[i] + images[j][i] + sign * period[i];
+                        if shifted >= min[i] - reach && shifted <= max[i] + reach {
+                            let mut image = images[j];
+                            image[i] += sign * period[i];
+                            images.push(image);
+                        }
+                    }
+                }
+            }
+        }
+        for shift in images {
+            let cell = self.volume.containing_cell(point + shift);
+            for i in 0..3_usize.pow(N as u32) {
+                let offset =
+                    from_linear(i, Vector::<_, N>::repeat(3)).cast::<i32>() - Vector::repeat(1);
+                if let Some(adj) = self.point_grid.get(&(cell + offset)) {
+                    for &adj in adj {
+                        if adj != point_index as u32 {
+                            f(adj, self.points[adj as usize] - shift);
+                        }
                     }
                 }
             }
@@ -68,6 +104,7 @@ impl<V: VolumeCore<N>, const N: usize> Solver<V, N> {
         for (point, delta) in self.points.iter_mut().zip(deltas) {
             *point += delta * delta_factor;
         }
+        self.wrap_points();
         self.max_penetration = max_penetration;
     }
     pub fn step_boundary(&mut self, delta_factor: f32) {
@@ -80,6 +117,7 @@ impl<V: VolumeCore<N>, const N: usize> Solver<V, N> {
                     self.boundary_penetration.max(self.volume.distance(*point));
             }
         }
+        self.wrap_points();
     }
     pub fn solve(&mut self, max_iters: usize, cutoff: f32) -> usize {
         let mut iters = 0;
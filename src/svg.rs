@@ -0,0 +1,624 @@
+use std::f32::consts::{PI, TAU};
+use std::fmt::{self, Display, Formatter, Write};
+
+use nalgebra::{Rotation2, Vector2};
+use path::{Path, Segment};
+use shape::Polygon;
+
+use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SvgError {
+    /// A character which doesn't start a command or number, at the given byte offset.
+    UnexpectedCharacter {
+        position: usize,
+        character: char,
+    },
+    ExpectedNumber {
+        position: usize,
+    },
+    ExpectedFlag {
+        position: usize,
+    },
+    /// Path data which doesn't start with a moveto command.
+    MissingMoveTo {
+        position: usize,
+    },
+    /// An element without the attribute holding its geometry.
+    MissingAttribute {
+        element: &'static str,
+    },
+}
+impl Display for SvgError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SvgError::UnexpectedCharacter {
+                position,
+                character,
+            } => write!(f, "unexpected character {:?} at {}", character, position),
+            SvgError::ExpectedNumber { position } => write!(f, "expected a number at {}", position),
+            SvgError::ExpectedFlag { position } => write!(f, "expected a flag at {}", position),
+            SvgError::MissingMoveTo { position } => {
+                write!(f, "expected a moveto command at {}", position)
+            }
+            SvgError::MissingAttribute { element } => {
+                write!(f, "<{}> element is missing its geometry attribute", element)
+            }
+        }
+    }
+}
+impl std::error::Error for SvgError {}
+
+struct Parser<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+impl Parser<'_> {
+    fn skip_separators(&mut self) {
+        while self
+            .data
+            .get(self.position)
+            .is_some_and(|c| c.is_ascii_whitespace() || *c == b',')
+        {
+            self.position += 1;
+        }
+    }
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_separators();
+        self.data.get(self.position).copied()
+    }
+    fn has_number(&mut self) -> bool {
+        self.peek()
+            .is_some_and(|c| c.is_ascii_digit() || matches!(c, b'-' | b'+' | b'.'))
+    }
+    fn number(&mut self) -> Result<f32, SvgError> {
+        self.skip_separators();
+        let start = self.position;
+        let digits = |parser: &mut Self| {
+            let start = parser.position;
+            while parser
+                .data
+                .get(parser.position)
+                .is_some_and(u8::is_ascii_digit)
+            {
+                parser.position += 1;
+            }
+            parser.position > start
+        };
+        if matches!(self.data.get(self.position), Some(b'-' | b'+')) {
+            self.position += 1;
+        }
+        let mut valid = digits(self);
+        if self.data.get(self.position) == Some(&b'.') {
+            self.position += 1;
+            valid |= digits(self);
+        }
+        if !valid {
+            self.position = start;
+            return Err(SvgError::ExpectedNumber { position: start });
+        }
+        if matches!(self.data.get(self.position), Some(b'e' | b'E')) {
+            let mantissa_end = self.position;
+            self.position += 1;
+            if matches!(self.data.get(self.position), Some(b'-' | b'+')) {
+                self.position += 1;
+            }
+            if !digits(self) {
+                self.position = mantissa_end;
+            }
+        }
+        std::str::from_utf8(&self.data[start..self.position])
+            .unwrap()
+            .parse()
+            .map_err(|_| SvgError::ExpectedNumber { position: start })
+    }
+    fn flag(&mut self) -> Result<bool, SvgError> {
+        let flag = match self.peek() {
+            Some(b'0') => false,
+            Some(b'1') => true,
+            _ => {
+                return Err(SvgError::ExpectedFlag {
+                    position: self.position,
+                })
+            }
+        };
+        self.position += 1;
+        Ok(flag)
+    }
+    fn point(&mut self) -> Result<Vector2<f32>, SvgError> {
+        Ok(Vector2::new(self.number()?, self.number()?))
+    }
+}
+
+/// Parses SVG path data into closed rings. Every subpath is treated as closed, as it is when filled.
+///
+/// See <https://www.w3.org/TR/SVG2/paths.html#PathData>.
+pub fn parse_path(data: &str) -> Result<Vec<Vec<Segment<2>>>, SvgError> {
+    let mut parser = Parser {
+        data: data.as_bytes(),
+        position: 0,
+    };
+    let mut rings = vec![];
+    let mut path: Option<Path> = None;
+    // Tracked separately from the path, since arcs don't end exactly on their endpoints.
+    let mut current = Vector2::zeros();
+    let mut start = Vector2::zeros();
+    let mut command = None;
+    while let Some(c) = parser.peek() {
+        let position = parser.position;
+        if c.is_ascii_alphabetic() {
+            parser.position += 1;
+            command = Some(c);
+        } else if command.is_none() || !parser.has_number() {
+            return Err(SvgError::UnexpectedCharacter {
+                position,
+                character: data[position..].chars().next().unwrap(),
+            });
+        }
+        let c = command.unwrap();
+        let relative = c.is_ascii_lowercase();
+        let offset = if relative { current } else { Vector2::zeros() };
+        let c = c.to_ascii_uppercase();
+        if c == b'M' {
+            if let Some(path) = path.take() {
+                rings.push(path.close());
+            }
+            current = offset + parser.point()?;
+            start = current;
+            path = Some(Path::new(current));
+            // Further coordinate pairs are implicit lineto commands.
+            command = Some(if relative { b'l' } else { b'L' });
+            continue;
+        }
+        if c == b'Z' {
+            if let Some(path) = path.take() {
+                rings.push(path.close());
+            }
+            current = start;
+            command = None;
+            continue;
+        }
+        let segment_path = match path.take() {
+            Some(path) => path,
+            // A command after closepath starts a new subpath at the same point.
+            None if !rings.is_empty() => Path::new(current),
+            None => return Err(SvgError::MissingMoveTo { position }),
+        };
+        let (next, end) = match c {
+            b'L' => {
+                let end = offset + parser.point()?;
+                (segment_path.line_to(end), end)
+            }
+            b'H' => {
+                let end = Vector2::new(parser.number()? + offset.x, current.y);
+                (segment_path.line_to(end), end)
+            }
+            b'V' => {
+                let end = Vector2::new(current.x, parser.number()? + offset.y);
+                (segment_path.line_to(end), end)
+            }
+            b'Q' => {
+                let control = offset + parser.point()?;
+                let end = offset + parser.point()?;
+                (segment_path.quad_to(control, end), end)
+            }
+            b'C' => {
+                let control_a = offset + parser.point()?;
+                let control_b = offset + parser.point()?;
+                let end = offset + parser.point()?;
+                (segment_path.cubic_to(control_a, control_b, end), end)
+            }
+            b'A' => {
+                let radii = Vector2::new(parser.number()?, parser.number()?);
+                let rotation = parser.number()?.to_radians();
+                let large_arc = parser.flag()?;
+                let sweep = parser.flag()?;
+                let end = offset + parser.point()?;
+                (
+                    arc_to(
+                        segment_path,
+                        current,
+                        radii,
+                        rotation,
+                        large_arc,
+                        sweep,
+                        end,
+                    ),
+                    end,
+                )
+            }
+            _ => {
+                return Err(SvgError::UnexpectedCharacter {
+                    position,
+                    character: c as char,
+                })
+            }
+        };
+        path = Some(next);
+        current = end;
+    }
+    if let Some(path) = path {
+        rings.push(path.close());
+    }
+    rings.retain(|ring| !ring.is_empty());
+    Ok(rings)
+}
+
+// https://www.w3.org/TR/SVG2/implnote.html#ArcImplementationNotes
+fn arc_to(
+    path: Path,
+    start: Vector2<f32>,
+    radii: Vector2<f32>,
+    rotation: f32,
+    large_arc: bool,
+    sweep: bool,
+    end: Vector2<f32>,
+) -> Path {
+    let mut radii = radii.abs();
+    if start == end {
+        return path;
+    }
+    if radii.x == 0.0 || radii.y == 0.0 {
+        return path.line_to(end);
+    }
+    let rotation = Rotation2::new(rotation);
+    let p = rotation.inverse() * ((start - end) / 2.0);
+    let lambda = (p.x / radii.x).powi(2) + (p.y / radii.y).powi(2);
+    if lambda > 1.0 {
+        radii *= lambda.sqrt();
+    }
+    let (rx2, ry2) = (radii.x * radii.x, radii.y * radii.y);
+    let numerator = rx2 * ry2 - rx2 * p.y * p.y - ry2 * p.x * p.x;
+    let denominator = rx2 * p.y * p.y + ry2 * p.x * p.x;
+    let scale =
+        (numerator / denominator).max(0.0).sqrt() * if large_arc == sweep { -1.0 } else { 1.0 };
+    let center_prime = Vector2::new(radii.x * p.y / radii.y, -radii.y * p.x / radii.x) * scale;
+    let center = rotation * center_prime + (start + end) / 2.0;
+
+    let angle = |v: Vector2<f32>| v.y.atan2(v.x);
+    let start_dir = (p - center_prime).component_div(&radii);
+    let end_dir = (-p - center_prime).component_div(&radii);
+    let start_angle = angle(start_dir);
+    let mut delta = (angle(end_dir) - start_angle).rem_euclid(TAU);
+    if !sweep && delta > 0.0 {
+        delta -= TAU;
+    }
+
+    if (radii.x - radii.y).abs() <= radii.x.max(radii.y) * 1e-6 {
+        return path.arc(center, delta);
+    }
+    // Elliptical arcs are approximated with cubics, with at most a quarter turn each.
+    let count = (delta.abs() / (PI / 2.0)).ceil().max(1.0) as usize;
+    let step = delta / count as f32;
+    let handle = 4.0 / 3.0 * (step / 4.0).tan();
+    let ellipse = |a: f32| center + rotation * Vector2::new(radii.x * a.cos(), radii.y * a.sin());
+    let tangent = |a: f32| rotation * Vector2::new(-radii.x * a.sin(), radii.y * a.cos());
+    let mut path = path;
+    for i in 0..count {
+        let a0 = start_angle + step * i as f32;
+        let a1 = a0 + step;
+        let segment_end = if i + 1 == count { end } else { ellipse(a1) };
+        let control_a = path.current() + tangent(a0) * handle;
+        path = path.cubic_to(control_a, segment_end - tangent(a1) * handle, segment_end);
+    }
+    path
+}
+
+/// Finds the value of an attribute in the text of a tag.
+fn attribute<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = tag;
+    while let Some(index) = rest.find(name) {
+        let preceded_by_space = rest[..index]
+            .chars()
+            .next_back()
+            .is_some_and(char::is_whitespace);
+        rest = &rest[index + name.len()..];
+        let after = rest.trim_start();
+        if !preceded_by_space || !after.starts_with('=') {
+            continue;
+        }
+        let value = after[1..].trim_start();
+        let quote = value.chars().next()?;
+        if quote != '"' && quote != '\'' {
+            return None;
+        }
+        let value = &value[1..];
+        return value.find(quote).map(|end| &value[..end]);
+    }
+    None
+}
+
+impl Polygon<2> {
+    pub fn add_svg_path(mut self, data: &str) -> Result<Self, SvgError> {
+        for ring in parse_path(data)? {
+            if ring
+                .iter()
+                .all(|segment| matches!(segment, Segment::Line(..)))
+            {
+                let vertices = ring
+                    .iter()
+                    .map(|segment| segment.start())
+                    .collect::<Vec<_>>();
+                self = self.add_polygon(&vertices);
+            } else {
+                self = self.add_segments(&ring);
+            }
+        }
+        Ok(self)
+    }
+    pub fn from_svg_path(data: &str) -> Result<Self, SvgError> {
+        Self::new().add_svg_path(data)
+    }
+    /// Reads the `<path>` and `<polygon>` elements of an SVG document.
+    /// Transforms and styles are ignored.
+    pub fn from_svg(document: &str) -> Result<Self, SvgError> {
+        let mut polygon = Self::new();
+        let mut rest = document;
+        while let Some(index) = rest.find('<') {
+            rest = &rest[index + 1..];
+            let tag = &rest[..rest.find('>').unwrap_or(rest.len())];
+            let name = tag
+                .split(|c: char| c.is_whitespace() || c == '/')
+                .next()
+                .unwrap();
+            if name == "path" {
+                let data =
+                    attribute(tag, "d").ok_or(SvgError::MissingAttribute { element: "path" })?;
+                polygon = polygon.add_svg_path(data)?;
+            } else if name == "polygon" {
+                let points = attribute(tag, "points")
+                    .ok_or(SvgError::MissingAttribute { element: "polygon" })?;
+                let mut parser = Parser {
+                    data: points.as_bytes(),
+                    position: 0,
+                };
+                let mut vertices = vec![];
+                while parser.peek().is_some() {
+                    vertices.push(parser.point()?);
+                }
+                if !vertices.is_empty() {
+                    polygon = polygon.add_polygon(&vertices);
+                }
+            }
+        }
+        Ok(polygon)
+    }
+
+    /// Writes the polygon as SVG path data, which can be read back by [`Polygon::from_svg_path`].
+    pub fn to_svg_path(&self) -> String {
+        let mut data = String::new();
+        for ring in self.polygons() {
+            for (i, v) in ring.iter().enumerate() {
+                let command = if i == 0 { 'M' } else { 'L' };
+                write!(data, "{}{} {} ", command, v.x, v.y).unwrap();
+            }
+            data.push_str("Z ");
+        }
+        for ring in self.paths().iter().filter(|ring| !ring.is_empty()) {
+            let start = ring[0].start();
+            write!(data, "M{} {} ", start.x, start.y).unwrap();
+            for segment in ring {
+                match *segment {
+                    Segment::Line(_, b) => write!(data, "L{} {} ", b.x, b.y),
+                    Segment::Quadratic([_, c, b]) => {
+                        write!(data, "Q{} {} {} {} ", c.x, c.y, b.x, b.y)
+                    }
+                    Segment::Cubic([_, c, d, b]) => {
+                        write!(data, "C{} {} {} {} {} {} ", c.x, c.y, d.x, d.y, b.x, b.y)
+                    }
+                    Segment::Arc { u, v, sweep, .. } => {
+                        // A single SVG arc can't cover a full turn, and is unstable close to one.
+                        let count = if sweep > 1.5 * PI { 2 } else { 1 };
+                        let large_arc = sweep / count as f32 > PI;
+                        let radius = u.norm();
+                        let positive = u.perp(&v) > 0.0;
+                        for i in 1..=count {
+                            let b = segment.point(i as f32 / count as f32);
+                            write!(
+                                data,
+                                "A{} {} 0 {} {} {} {} ",
+                                radius, radius, large_arc as u8, positive as u8, b.x, b.y
+                            )
+                            .unwrap();
+                        }
+                        Ok(())
+                    }
+                }
+                .unwrap();
+            }
+            data.push_str("Z ");
+        }
+        data.pop();
+        data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vertices(data: &str) -> Vec<Vec<Vector2<f32>>> {
+        parse_path(data)
+            .unwrap()
+            .iter()
+            .map(|ring| ring.iter().map(Segment::start).collect())
+            .collect()
+    }
+
+    #[test]
+    fn lines_and_relative_commands() {
+        let square = [
+            Vector2::new(1.0, 1.0),
+            Vector2::new(3.0, 1.0),
+            Vector2::new(3.0, 3.0),
+            Vector2::new(1.0, 3.0),
+        ];
+        assert_eq!(vertices("M1 1 L3 1 L3 3 L1 3 Z"), [square]);
+        assert_eq!(vertices("m1,1 l2,0 v2 h-2 z"), [square]);
+        assert_eq!(vertices("M1 1H3V3H1"), [square]);
+        // Coordinate pairs after a moveto are linetos.
+        assert_eq!(vertices("M1 1 3 1 3 3 1 3"), [square]);
+        // Numbers needn't be separated where their signs or decimal points tell them apart.
+        assert_eq!(
+            vertices("M1-1.5.5 2e0 3 4"),
+            [[
+                Vector2::new(1.0, -1.5),
+                Vector2::new(0.5, 2.0),
+                Vector2::new(3.0, 4.0)
+            ]]
+        );
+        // A relative moveto after closepath starts from the start of the previous subpath.
+        let rings = vertices("M1 1 h2 v2 h-2 z m4 0 h2 v2 h-2 z");
+        assert_eq!(rings.len(), 2);
+        assert_eq!(rings[1][0], Vector2::new(5.0, 1.0));
+    }
+
+    #[test]
+    fn curves() {
+        let rings = parse_path("M0 0 Q1 2 2 0 c0 -1 2 -1 2 0").unwrap();
+        assert_eq!(rings.len(), 1);
+        assert_eq!(
+            rings[0][0],
+            Segment::Quadratic([
+                Vector2::new(0.0, 0.0),
+                Vector2::new(1.0, 2.0),
+                Vector2::new(2.0, 0.0)
+            ])
+        );
+        assert_eq!(
+            rings[0][1],
+            Segment::Cubic([
+                Vector2::new(2.0, 0.0),
+                Vector2::new(2.0, -1.0),
+                Vector2::new(4.0, -1.0),
+                Vector2::new(4.0, 0.0)
+            ])
+        );
+        assert_eq!(rings[0].last().unwrap().end(), Vector2::zeros());
+    }
+
+    #[test]
+    fn arcs() {
+        // Half of a circle around (1, 0), with the flags packed together.
+        let polygon = Polygon::from_svg_path("M0 0 A1 1 0 01 2 0 Z").unwrap();
+        for i in 0..=16 {
+            let angle = PI + PI * i as f32 / 16.0;
+            let on_arc = Vector2::new(1.0 + angle.cos(), angle.sin());
+            assert!(polygon.distance(on_arc).abs() < 1e-3, "{on_arc}");
+        }
+        assert!(polygon.contains(Vector2::new(1.0, -0.5)));
+        assert!(!polygon.contains(Vector2::new(1.0, 0.5)));
+
+        // Radii too small to reach the end point are scaled up.
+        let polygon = Polygon::from_svg_path("M0 0 a0.1 0.1 0 1 0 2 0 z").unwrap();
+        assert!(polygon.distance(Vector2::new(1.0, 1.0)).abs() < 1e-3);
+        assert!(polygon.contains(Vector2::new(1.0, 0.5)));
+
+        // Half of an ellipse turned by a right angle, so its long axis is vertical.
+        let polygon = Polygon::from_svg_path("M0 0 A2 1 90 0 1 0 4").unwrap();
+        assert!(polygon.distance(Vector2::new(1.0, 2.0)).abs() < 1e-3);
+        assert!(polygon.distance(Vector2::new(0.6, 2.0 + 1.6)).abs() < 1e-3);
+        assert!(polygon.contains(Vector2::new(0.5, 2.0)));
+    }
+
+    #[test]
+    fn documents() {
+        let document = r#"<svg xmlns="http://www.w3.org/2000/svg">
+            <g transform="translate(100 100)" fill-rule="nonzero">
+                <path id="outer" d="M0 0 H10 V10 H0 Z" />
+                <polygon points="2,2 8,2 8,8 2,8"/>
+            </g>
+        </svg>"#;
+        let polygon = Polygon::from_svg(document).unwrap();
+        // The transform and the nonzero fill rule are ignored, so the inner square is a hole.
+        assert_eq!(polygon.fill_rule(), shape::FillRule::EvenOdd);
+        assert!(polygon.contains(Vector2::new(1.0, 5.0)));
+        assert!(!polygon.contains(Vector2::new(5.0, 5.0)));
+        assert!(!polygon.contains(Vector2::new(105.0, 105.0)));
+        let nonzero = polygon.with_fill_rule(shape::FillRule::NonZero);
+        assert!(nonzero.contains(Vector2::new(5.0, 5.0)));
+    }
+
+    #[test]
+    fn round_trip() {
+        let polygon = Polygon::new()
+            .add_polygon(&[
+                Vector2::new(100.0, 100.0),
+                Vector2::new(200.0, 100.0),
+                Vector2::new(200.0, 200.0),
+                Vector2::new(100.0, 200.0),
+            ])
+            .add_polygon(&[
+                Vector2::new(150.0, 150.0),
+                Vector2::new(150.0, 250.0),
+                Vector2::new(250.0, 200.0),
+            ])
+            .add_circle(30.0, Vector2::new(300.0, 300.0));
+        let data = polygon.to_svg_path();
+        let read = Polygon::from_svg_path(&data).unwrap();
+        assert_eq!(read.polygons(), polygon.polygons());
+        for x in (90..320).step_by(7) {
+            for y in (90..340).step_by(7) {
+                let point = Vector2::new(x as f32, y as f32);
+                let (a, b) = (polygon.distance(point), read.distance(point));
+                assert!((a - b).abs() < 1e-2, "{point} {a} {b}");
+            }
+        }
+        assert_eq!(read.to_svg_path(), data);
+    }
+
+    #[test]
+    fn malformed_input() {
+        assert_eq!(
+            parse_path("L1 1"),
+            Err(SvgError::MissingMoveTo { position: 0 })
+        );
+        assert_eq!(
+            parse_path("M1 1 L2"),
+            Err(SvgError::ExpectedNumber { position: 7 })
+        );
+        assert_eq!(
+            parse_path("M0 0 A1 1 0 2 0 1 1"),
+            Err(SvgError::ExpectedFlag { position: 12 })
+        );
+        assert_eq!(
+            parse_path("M0 0 X1 1"),
+            Err(SvgError::UnexpectedCharacter {
+                position: 5,
+                character: 'X'
+            })
+        );
+        assert_eq!(
+            parse_path("1 1"),
+            Err(SvgError::UnexpectedCharacter {
+                position: 0,
+                character: '1'
+            })
+        );
+        assert_eq!(
+            parse_path("M0 0 L1 1 é"),
+            Err(SvgError::UnexpectedCharacter {
+                position: 10,
+                character: 'é'
+            })
+        );
+        assert!(parse_path("M0 0 L- 1").is_err());
+        assert!(parse_path("M0 0 L1 1e").is_err());
+        assert_eq!(
+            Polygon::from_svg("<svg><path/></svg>").err(),
+            Some(SvgError::MissingAttribute { element: "path" })
+        );
+        assert!(Polygon::from_svg("<polygon points=\"0 0 1\">").is_err());
+        assert!(Polygon::from_svg("<path d=\"M0 0 L1").is_err());
+        // Degenerate but well-formed input doesn't panic.
+        for data in [
+            "",
+            "M0 0",
+            "M0 0 Z Z",
+            "M0 0 A0 0 0 0 0 1 1",
+            "M0 0 A1 1 0 0 0 0 0",
+        ] {
+            parse_path(data).unwrap();
+        }
+    }
+}
@@ -0,0 +1,465 @@
+use std::cell::Cell;
+
+use bvh::Bvh;
+use nalgebra::{Rotation3, Unit, Vector3};
+use path::Segment;
+
+use super::*;
+
+/// A cross-section moved along a path, such as a bent pipe or a channel.
+///
+/// The x and y axes of the cross-section are carried along the path with rotation-minimizing frames.
+/// The x axis starts out as the world x axis made perpendicular to the path, or the world y axis
+/// if the path starts along x, so sweeping along the z axis is the same as [`Extrude3`](crate::shape::Extrude3).
+/// Sharp corners of the path are mitered on the inside and rounded on the outside.
+///
+/// Outside, the distance is exact if the cross-section's is. Inside, it is the depth within the cross-section
+/// through the nearest point of the path, which doesn't overestimate as long as the cross-section contains the path
+/// and is small compared to its radius of curvature, and the path doesn't come back near itself.
+#[derive(Debug, Clone)]
+pub struct Sweep<V: VolumeCore<2>> {
+    base: V,
+    points: Vec<Vector3<f32>>,
+    // The x and y axes of the cross-section along each segment.
+    frames: Vec<(Vector3<f32>, Vector3<f32>)>,
+    // The axis and angle of the rotation from each segment's frame to the next one.
+    turns: Vec<(Unit<Vector3<f32>>, f32)>,
+    // The distance from the path that the cross-section reaches.
+    radius: f32,
+    // Over the bounds of the part of the sweep along each segment.
+    bvh: Bvh<3>,
+}
+impl<V: VolumeCore<2>> Sweep<V> {
+    /// Sweeps along straight lines between the points.
+    pub fn along_polyline(base: V, points: &[Vector3<f32>]) -> Self {
+        let mut points = points.to_vec();
+        points.dedup();
+        assert!(
+            points.len() >= 2,
+            "Sweep path must have at least two distinct points"
+        );
+        let (min, max) = (base.min_bound(), base.max_bound());
+        let radius = min.abs().sup(&max.abs()).norm();
+
+        let tangents = points
+            .windows(2)
+            .map(|w| (w[1] - w[0]).normalize())
+            .collect::<Vec<_>>();
+        let first = tangents[0];
+        let x = Vector3::x() - first * first.x;
+        let x = if x.norm() > 1e-3 {
+            x.normalize()
+        } else {
+            (Vector3::y() - first * first.y).normalize()
+        };
+        let mut frames = vec![(x, first.cross(&x))];
+        let mut turns = vec![];
+        for w in tangents.windows(2) {
+            let axis = Unit::try_new(w[0].cross(&w[1]), 0.0)
+                .unwrap_or_else(|| Unit::new_normalize(any_perpendicular(w[0])));
+            let angle = w[0].cross(&w[1]).norm().atan2(w[0].dot(&w[1]));
+            // Keep the frame orthonormal over many segments.
+            let x = Rotation3::from_axis_angle(&axis, angle) * frames.last().unwrap().0;
+            let x = (x - w[1] * w[1].dot(&x)).normalize();
+            frames.push((x, w[1].cross(&x)));
+            turns.push((axis, angle));
+        }
+
+        let bounds = points
+            .windows(2)
+            .map(|w| {
+                (
+                    w[0].inf(&w[1]) - Vector3::repeat(radius),
+                    w[0].sup(&w[1]) + Vector3::repeat(radius),
+                )
+            })
+            .collect::<Vec<_>>();
+        Sweep {
+            base,
+            points,
+            frames,
+            turns,
+            radius,
+            bvh: Bvh::new(&bounds),
+        }
+    }
+    /// Sweeps along a uniform Catmull-Rom spline through the points, which is flattened finely enough
+    /// that the difference is negligible.
+    pub fn along_spline(base: V, points: &[Vector3<f32>]) -> Self {
+        let mut points = points.to_vec();
+        points.dedup();
+        assert!(
+            points.len() >= 2,
+            "Sweep path must have at least two distinct points"
+        );
+        let (min, max) = points.iter().fold(
+            (
+                Vector3::repeat(f32::INFINITY),
+                Vector3::repeat(f32::NEG_INFINITY),
+            ),
+            |(min, max), p| (min.inf(p), max.sup(p)),
+        );
+        let tolerance = ((max - min).amax() + (base.max_bound() - base.min_bound()).amax()) * 1e-4;
+        // The end points are repeated to give the spline tangents at its ends.
+        let at = |i: isize| points[i.clamp(0, points.len() as isize - 1) as usize];
+        let mut flattened = vec![points[0]];
+        for i in 0..points.len() as isize - 1 {
+            let span = Segment::Cubic([
+                at(i),
+                at(i) + (at(i + 1) - at(i - 1)) / 6.0,
+                at(i + 1) - (at(i + 2) - at(i)) / 6.0,
+                at(i + 1),
+            ]);
+            flattened.extend(span.flatten(tolerance));
+        }
+        Self::along_polyline(base, &flattened)
+    }
+    pub fn base(&self) -> &V {
+        &self.base
+    }
+    /// The points of the path, with splines flattened.
+    pub fn path(&self) -> &[Vector3<f32>] {
+        &self.points
+    }
+    // Returns the distance and gradient.
+    fn query(&self, point: Vector3<f32>) -> (f32, Vector3<f32>) {
+        let (index, _) = self
+            .bvh
+            .nearest(point, |i| {
+                distance_to_line(self.points[i as usize], self.points[i as usize + 1], point)
+            })
+            .unwrap();
+        let i = index as usize;
+        let last = self.frames.len() - 1;
+        let (along, length) = self.along(i, point);
+        // The corner before or after the segment, if the nearest point of the path is on it.
+        let corner = if along < 0.0 && i > 0 {
+            Some(i - 1)
+        } else if along > length && i < last {
+            Some(i)
+        } else {
+            None
+        };
+        // The cross-section through the nearest point of the path. On the inside of sharp corners,
+        // that is the one of the segment on the same side of the miter plane.
+        let nearest = corner
+            .and_then(|c| self.corner(c, point))
+            .unwrap_or_else(|| {
+                let i = corner.map_or(i, |c| {
+                    let miter = self.tangent(c) + self.tangent(c + 1);
+                    c + usize::from((point - self.points[c + 1]).dot(&miter) > 0.0)
+                });
+                let (along, length) = self.along(i, point);
+                let start = if i == 0 { -along } else { f32::NEG_INFINITY };
+                let end = if i == last {
+                    along - length
+                } else {
+                    f32::NEG_INFINITY
+                };
+                self.segment(i, point, start, end)
+            });
+        let result = if nearest.0 <= 0.0 {
+            nearest
+        } else {
+            // Outside, the nearest part of the surface may be on another cross-section, such as across a bend,
+            // so every piece of the sweep which could be closer is checked.
+            let best = Cell::new(nearest.0);
+            let (index, dist) = self
+                .bvh
+                .nearest(point, |i| {
+                    let (a, b) = (self.points[i as usize], self.points[i as usize + 1]);
+                    if distance_to_line(a, b, point) - self.radius >= best.get() {
+                        return f32::INFINITY;
+                    }
+                    let dist = self.piece(i as usize, point).0;
+                    best.set(best.get().min(dist));
+                    dist
+                })
+                .unwrap();
+            if dist >= nearest.0 {
+                nearest
+            } else if dist > -nearest.0 {
+                self.piece(index as usize, point)
+            } else {
+                // The point is inside another piece, but how deep is only known within the nearest cross-section.
+                (-nearest.0, -nearest.1)
+            }
+        };
+        // Inside, the distance within the cross-section misses the faces where the sweep ends.
+        if result.0 <= 0.0 {
+            self.faces(i, point)
+                .fold(result, |a, b| if b.0 > a.0 { b } else { a })
+        } else {
+            result
+        }
+    }
+    // The negated distances to the cross-sections at the ends of the path, and at corners after or before
+    // segment `i` which turn by more than a right angle, where they aren't covered by the next segment.
+    // They are bounded by the distances to the discs around them.
+    fn faces(
+        &self,
+        i: usize,
+        point: Vector3<f32>,
+    ) -> impl Iterator<Item = (f32, Vector3<f32>)> + '_ {
+        let last = self.frames.len() - 1;
+        let mut faces = vec![
+            (self.points[0], -self.tangent(0)),
+            (self.points[last + 1], self.tangent(last)),
+        ];
+        for c in i.saturating_sub(1)..(i + 1).min(last) {
+            if self.turns[c].1 > std::f32::consts::FRAC_PI_2 {
+                faces.push((self.points[c + 1], self.tangent(c)));
+                faces.push((self.points[c + 1], -self.tangent(c + 1)));
+            }
+        }
+        faces.into_iter().map(move |(center, normal)| {
+            let offset = point - center;
+            let radial = offset - normal * offset.dot(&normal);
+            let toward = radial * (self.radius / radial.norm()).min(1.0) - offset;
+            (-toward.norm(), toward.try_normalize(0.0).unwrap_or(normal))
+        })
+    }
+    fn tangent(&self, i: usize) -> Vector3<f32> {
+        let (x, y) = self.frames[i];
+        x.cross(&y)
+    }
+    // How far along segment `i` the point is, and the length of the segment.
+    fn along(&self, i: usize, point: Vector3<f32>) -> (f32, f32) {
+        let (a, b) = (self.points[i], self.points[i + 1]);
+        let length = (b - a).norm();
+        ((point - a).dot(&(b - a)) / length, length)
+    }
+    // The distance and gradient of the part of the sweep along segment `i` and around the corner after it.
+    fn piece(&self, i: usize, point: Vector3<f32>) -> (f32, Vector3<f32>) {
+        let (along, length) = self.along(i, point);
+        let segment = self.segment(i, point, -along, along - length);
+        match self.corner(i, point) {
+            Some(corner) if corner.0 < segment.0 => corner,
+            _ => segment,
+        }
+    }
+    // The cross-section moved along segment `i`, cut off by planes the given distances past its ends.
+    fn segment(&self, i: usize, point: Vector3<f32>, start: f32, end: f32) -> (f32, Vector3<f32>) {
+        let section = self.section(self.points[i], self.frames[i], point);
+        let (cap, normal) = if start > end {
+            (start, -self.tangent(i))
+        } else {
+            (end, self.tangent(i))
+        };
+        if section.0 > 0.0 && cap > 0.0 {
+            let dist = section.0.hypot(cap);
+            (dist, (section.1 * section.0 + normal * cap) / dist)
+        } else if cap > section.0 {
+            (cap, normal)
+        } else {
+            section
+        }
+    }
+    // The cross-section turning around the corner after segment `i`, if the point lies in the plane of one of
+    // its turned copies. Otherwise, the nearest one is at the end of a segment.
+    fn corner(&self, i: usize, point: Vector3<f32>) -> Option<(f32, Vector3<f32>)> {
+        let (axis, angle) = *self.turns.get(i)?;
+        let (x, y) = self.frames[i];
+        let (tangent, side) = (x.cross(&y), axis.cross(&x.cross(&y)));
+        let vertex = self.points[i + 1];
+        let offset = point - vertex;
+        // The copy turned by this angle contains the point, as does the one turned by half a turn more.
+        let partial = (-offset.dot(&tangent)).atan2(offset.dot(&side));
+        [
+            partial - std::f32::consts::PI,
+            partial,
+            partial + std::f32::consts::PI,
+        ]
+        .into_iter()
+        .filter(|&partial| (0.0..=angle).contains(&partial))
+        .map(|partial| {
+            let turn = Rotation3::from_axis_angle(&axis, partial);
+            self.section(vertex, (turn * x, turn * y), point)
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+    }
+    // The distance and gradient within the cross-section placed at the origin with the axes.
+    fn section(
+        &self,
+        origin: Vector3<f32>,
+        (x, y): (Vector3<f32>, Vector3<f32>),
+        point: Vector3<f32>,
+    ) -> (f32, Vector3<f32>) {
+        let local = Vector2::new((point - origin).dot(&x), (point - origin).dot(&y));
+        let grad = self.base.gradient(local);
+        (self.base.distance(local), x * grad.x + y * grad.y)
+    }
+}
+impl<V: VolumeCore<2>> VolumeCore<3> for Sweep<V> {
+    fn distance(&self, point: Vector3<f32>) -> f32 {
+        self.query(point).0
+    }
+    fn gradient(&self, point: Vector3<f32>) -> Vector3<f32> {
+        self.query(point).1
+    }
+    fn contains(&self, point: Vector3<f32>) -> bool {
+        self.distance(point) <= 0.0
+    }
+    fn min_bound(&self) -> Vector3<f32> {
+        self.bvh.nodes[0].min
+    }
+    fn max_bound(&self) -> Vector3<f32> {
+        self.bvh.nodes[0].max
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ext::Volume2d;
+    use shape::{Ball, Cuboid};
+
+    use super::*;
+
+    #[test]
+    fn straight_sweep_is_an_extrusion() {
+        let base = Cuboid::new(Vector2::new(1.0, 0.5)) + Vector2::new(0.2, 0.1);
+        let sweep = base.sweep(&[Vector3::new(0.0, 0.0, -1.0), Vector3::new(0.0, 0.0, 2.0)]);
+        let extrusion = base.extrude(-1.0, 2.0);
+        for x in -8..8 {
+            for y in -8..8 {
+                for z in -8..8 {
+                    let point = Vector3::new(x as f32 + 0.5, y as f32 + 0.5, z as f32 + 0.5) * 0.3;
+                    let (dist, expected) = (sweep.distance(point), extrusion.distance(point));
+                    assert!((dist - expected).abs() < 1e-6, "{point}: {dist} {expected}");
+                    assert_eq!(sweep.contains(point), extrusion.contains(point), "{point}");
+                }
+            }
+        }
+    }
+
+    // The cross-sections placed along the polyline, as origins and axes, including the ones turning around
+    // the corners. The axes start out as described for `Sweep`, and are turned as little as possible at corners.
+    fn sections(points: &[Vector3<f32>]) -> Vec<(Vector3<f32>, Vector3<f32>, Vector3<f32>)> {
+        let first = (points[1] - points[0]).normalize();
+        let x = Vector3::x() - first * first.x;
+        let mut x = if x.norm() > 1e-3 {
+            x.normalize()
+        } else {
+            (Vector3::y() - first * first.y).normalize()
+        };
+        let mut sections = vec![];
+        for (i, w) in points.windows(2).enumerate() {
+            let direction = (w[1] - w[0]).normalize();
+            let y = direction.cross(&x);
+            let count = ((w[1] - w[0]).norm() / 1e-2).ceil() as usize;
+            sections.extend((0..=count).map(|j| (w[0].lerp(&w[1], j as f32 / count as f32), x, y)));
+            if let Some(next) = points.get(i + 2) {
+                let turn =
+                    Rotation3::rotation_between(&direction, &(next - w[1]).normalize()).unwrap();
+                let (axis, angle) = turn.axis_angle().unwrap();
+                sections.extend((0..=80).map(|j| {
+                    let partial = Rotation3::from_axis_angle(&axis, angle * j as f32 / 80.0);
+                    (w[1], partial * x, partial * y)
+                }));
+                x = turn * x;
+            }
+        }
+        sections
+    }
+
+    // Compares a channel with a rectangular cross-section which is off-center, swept along the polyline,
+    // with the union of its cross-sections. Outside, the distance should match, and inside, a ball of the depth should fit.
+    fn check_channel(points: &[Vector3<f32>], samples: impl Iterator<Item = Vector3<f32>>) {
+        let base = Cuboid::new(Vector2::new(0.3, 0.1)) + Vector2::new(0.0, 0.05);
+        let channel = base.sweep(points);
+        let sections = sections(points);
+        let union = |point: Vector3<f32>| {
+            sections
+                .iter()
+                .fold(f32::INFINITY, |best, &(origin, x, y)| {
+                    let offset = point - origin;
+                    // Sections too far away can be skipped.
+                    if offset.norm() - 0.5 >= best {
+                        return best;
+                    }
+                    let height = offset.dot(&x.cross(&y));
+                    let local = Vector2::new(offset.dot(&x), offset.dot(&y));
+                    best.min(height.hypot(base.distance(local).max(0.0)))
+                })
+        };
+        // The directions along the axes and to the corners of a cube.
+        let directions = (0..27)
+            .map(|i| Vector3::new(i % 3, i / 3 % 3, i / 9).cast::<f32>() - Vector3::repeat(1.0))
+            .filter(|d| d.abs().sum() != 2.0)
+            .filter_map(|d| d.try_normalize(0.0))
+            .collect::<Vec<_>>();
+        let mut inside = 0;
+        for point in samples {
+            let (dist, expected) = (channel.distance(point), union(point));
+            if dist > 0.0 {
+                assert!((dist - expected).abs() < 1e-2, "{point}: {dist} {expected}");
+            } else {
+                inside += 1;
+                assert!(expected < 1e-2, "{point}: {dist} {expected}");
+                for direction in &directions {
+                    let edge = point + direction * (-dist - 1e-2).max(0.0);
+                    assert!(union(edge) < 1e-2, "{point}: {dist} {edge}");
+                }
+            }
+        }
+        assert!(inside > 20, "{inside}");
+    }
+
+    #[test]
+    fn bent_channel() {
+        let points = [
+            Vector3::zeros(),
+            Vector3::new(2.0, 0.0, 0.0),
+            Vector3::new(2.0, 2.0, 0.0),
+            Vector3::new(2.0, 2.0, 2.0),
+        ];
+        let mut rng = Pcg64Mcg::seed_from_u64(1);
+        let samples = (0..200).map(|_| {
+            let w = rng.gen_range(0..points.len() - 1);
+            // Half of the points are around the corners.
+            let t = if rng.gen() { 1.0 } else { rng.gen::<f32>() };
+            points[w].lerp(&points[w + 1], t) + Vector3::from_fn(|_, _| rng.gen_range(-0.4..0.4))
+        });
+        check_channel(&points, samples);
+    }
+
+    #[test]
+    fn channel_turning_back() {
+        // Turning by more than a right angle uncovers parts of the cross-sections at the corner.
+        let corner = Vector3::new(2.0, 0.0, 0.0);
+        let points = [Vector3::zeros(), corner, Vector3::new(0.5, 1.0, 0.5)];
+        let samples = (0..343).map(|i| {
+            let offset =
+                Vector3::new(i % 7, i / 7 % 7, i / 49).cast::<f32>() - Vector3::repeat(3.0);
+            corner + offset * 0.1
+        });
+        check_channel(&points, samples);
+    }
+
+    #[test]
+    fn spline_through_points() {
+        let points = [
+            Vector3::zeros(),
+            Vector3::new(1.0, 1.0, 0.0),
+            Vector3::new(2.0, 0.0, 1.0),
+            Vector3::new(3.0, 1.0, 1.0),
+        ];
+        let pipe = Ball::new(0.2).sweep_spline(&points);
+        for (i, point) in points.iter().enumerate() {
+            let nearest = pipe
+                .path()
+                .iter()
+                .map(|p| (p - point).norm())
+                .fold(f32::INFINITY, f32::min);
+            assert!(nearest < 1e-5, "{point}: {nearest}");
+            // The ends of the path are on the faces where the sweep ends.
+            let depth = if i == 0 || i == points.len() - 1 {
+                0.0
+            } else {
+                -0.2
+            };
+            let dist = pipe.distance(*point);
+            assert!((dist - depth).abs() < 1e-3, "{point}: {dist}");
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use nalgebra::{AbstractRotation, Isometry, Rotation, SMatrix};
+
+use super::*;
+
+/// A volume transformed by an invertible affine map, taking a point `x` in the inner volume
+/// to `matrix * x + translation`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transformed<V: VolumeCore<N>, const N: usize> {
+    volume: V,
+    matrix: SMatrix<f32, N, N>,
+    inverse: SMatrix<f32, N, N>,
+    translation: Vector<f32, N>,
+    // The smallest singular value of the matrix, used to keep `distance` conservative.
+    min_scale: f32,
+}
+impl<V: VolumeCore<N>, const N: usize> Transformed<V, N> {
+    /// Returns `None` if the matrix isn't invertible.
+    pub fn try_new(
+        volume: V,
+        matrix: SMatrix<f32, N, N>,
+        translation: Vector<f32, N>,
+    ) -> Option<Self> {
+        let inverse = matrix.try_inverse()?;
+        Some(Self {
+            volume,
+            matrix,
+            inverse,
+            translation,
+            min_scale: 1.0 / spectral_norm(&inverse),
+        })
+    }
+    pub fn new(volume: V, matrix: SMatrix<f32, N, N>, translation: Vector<f32, N>) -> Self {
+        Self::try_new(volume, matrix, translation).expect("Transform matrix is not invertible")
+    }
+    pub fn from_isometry<R: AbstractRotation<f32, N>>(
+        volume: V,
+        isometry: Isometry<f32, R, N>,
+    ) -> Self {
+        let matrix = SMatrix::from_fn(|i, j| {
+            let axis = Vector::from_fn(|k, _| if k == j { 1.0 } else { 0.0 });
+            isometry.rotation.transform_vector(&axis)[i]
+        });
+        Self {
+            volume,
+            matrix,
+            inverse: matrix.transpose(),
+            translation: isometry.translation.vector,
+            min_scale: 1.0,
+        }
+    }
+    pub fn from_rotation(volume: V, rotation: Rotation<f32, N>) -> Self {
+        let matrix = rotation.into_inner();
+        Self {
+            volume,
+            matrix,
+            inverse: matrix.transpose(),
+            translation: Vector::zeros(),
+            min_scale: 1.0,
+        }
+    }
+    pub fn inner(&self) -> &V {
+        &self.volume
+    }
+    pub fn into_inner(self) -> V {
+        self.volume
+    }
+    pub fn to_local(&self, point: Vector<f32, N>) -> Vector<f32, N> {
+        self.inverse * (point - self.translation)
+    }
+    pub fn to_world(&self, point: Vector<f32, N>) -> Vector<f32, N> {
+        self.matrix * point + self.translation
+    }
+    fn world_box(&self) -> (Vector<f32, N>, Vector<f32, N>) {
+        let min = self.volume.min_bound();
+        let max = self.volume.max_bound();
+        let center = self.to_world((min + max) / 2.0);
+        let extent = self.matrix.abs() * ((max - min) / 2.0);
+        (center, extent)
+    }
+}
+impl<V: VolumeCore<N>, const N: usize> VolumeCore<N> for Transformed<V, N> {
+    // This is only the exact nearest point if the transform preserves angles.
+    fn nearest_surface_point(&self, point: Vector<f32, N>) -> (Vector<f32, N>, bool) {
+        let (surface, inside) = self.volume.nearest_surface_point(self.to_local(point));
+        (self.to_world(surface), inside)
+    }
+    fn distance(&self, point: Vector<f32, N>) -> f32 {
+        self.volume.distance(self.to_local(point)) * self.min_scale
+    }
+    fn gradient(&self, point: Vector<f32, N>) -> Vector<f32, N> {
+        (self.inverse.transpose() * self.volume.gradient(self.to_local(point)))
+            .try_normalize(1e-6)
+            .unwrap_or(Vector::zeros())
+    }
+    fn contains(&self, point: Vector<f32, N>) -> bool {
+        self.volume.contains(self.to_local(point))
+    }
+    fn min_bound(&self) -> Vector<f32, N> {
+        let (center, extent) = self.world_box();
+        center - extent
+    }
+    fn max_bound(&self) -> Vector<f32, N> {
+        let (center, extent) = self.world_box();
+        center + extent
+    }
+    // Each periodic axis of the inner volume has to be mapped onto a world axis.
+    fn period(&self) -> Vector<f32, N> {
+        let mut period = Vector::zeros();
+        for (i, p) in self.volume.period().iter().enumerate() {
+            if *p > 0.0 {
+                let step = self.matrix.column(i) * *p;
+                let axis = step.iamax();
+                assert!(
+                    step.iter()
+                        .enumerate()
+                        .all(|(j, x)| j == axis || x.abs() <= step[axis].abs() * 1e-5),
+                    "Transformed periodic volumes must keep their periodic axes along the world axes"
+                );
+                period[axis] = step[axis].abs();
+            }
+        }
+        period
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Rotation2, Vector2};
+    use shape::Ball;
+
+    use super::*;
+
+    #[test]
+    fn periodic_axes() {
+        let ball = Ball::new(1.0).periodic(Vector2::new(4.0, 0.0));
+        let scaled = ball.scale(Vector2::new(-2.0, 1.0));
+        assert_eq!(scaled.period(), Vector2::new(8.0, 0.0));
+        let turned = ball.rotate(Rotation2::new(std::f32::consts::FRAC_PI_2));
+        assert_eq!(turned.period(), Vector2::new(0.0, 4.0));
+        assert!((turned.max_bound() - Vector2::new(1.0, 2.0)).norm() < 1e-5);
+    }
+
+    #[test]
+    #[should_panic(expected = "world axes")]
+    fn periodic_axes_at_an_angle() {
+        let ball = Ball::new(1.0).periodic(Vector2::new(4.0, 0.0));
+        ball.rotate(Rotation2::new(0.5)).period();
+    }
+}
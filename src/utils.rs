@@ -1,3 +1,5 @@
+use nalgebra::{DMatrix, DVector, SMatrix};
+
 use super::*;
 
 pub fn distance_to_line<const N: usize>(
@@ -15,12 +17,201 @@ pub fn project_line<const N: usize>(
 ) -> Vector<f32, N> {
     let c = b - a;
     let l2 = c.norm_squared();
+    if l2 == 0.0 {
+        return a;
+    }
     let proj = (x - a).dot(&c) / l2;
     let proj = proj.clamp(0.0, 1.0);
     a + proj * c
 }
 
-// Also, https://github.com/RenderKit/embree/blob/master/tutorials/common/math/closest_point.h
+/// Some unit vector perpendicular to the given one, which should be normalized or zero.
+pub fn any_perpendicular<const N: usize>(v: Vector<f32, N>) -> Vector<f32, N> {
+    let least_aligned = v.iamin();
+    let mut perpendicular = Vector::zeros();
+    perpendicular[least_aligned] = 1.0;
+    (perpendicular - v * v[least_aligned]).normalize()
+}
+
+// https://github.com/RenderKit/embree/blob/master/tutorials/common/math/closest_point.h
+pub fn project_triangle<const N: usize>(
+    a: Vector<f32, N>,
+    b: Vector<f32, N>,
+    c: Vector<f32, N>,
+    x: Vector<f32, N>,
+) -> Vector<f32, N> {
+    let ab = b - a;
+    let ac = c - a;
+    let ap = x - a;
+    let d1 = ab.dot(&ap);
+    let d2 = ac.dot(&ap);
+    if d1 <= 0.0 && d2 <= 0.0 {
+        return a;
+    }
+    let bp = x - b;
+    let d3 = ab.dot(&bp);
+    let d4 = ac.dot(&bp);
+    if d3 >= 0.0 && d4 <= d3 {
+        return b;
+    }
+    let cp = x - c;
+    let d5 = ab.dot(&cp);
+    let d6 = ac.dot(&cp);
+    if d6 >= 0.0 && d5 <= d6 {
+        return c;
+    }
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= 0.0 && d1 >= 0.0 && d3 <= 0.0 {
+        return a + ab * (d1 / (d1 - d3));
+    }
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= 0.0 && d2 >= 0.0 && d6 <= 0.0 {
+        return a + ac * (d2 / (d2 - d6));
+    }
+    let va = d3 * d6 - d5 * d4;
+    if va <= 0.0 && (d4 - d3) >= 0.0 && (d5 - d6) >= 0.0 {
+        return b + (c - b) * ((d4 - d3) / ((d4 - d3) + (d5 - d6)));
+    }
+    let denom = 1.0 / (va + vb + vc);
+    a + ab * (vb * denom) + ac * (vc * denom)
+}
+
+/// The nearest point to `x` in the convex hull of the points, found with
+/// [Wolfe's algorithm](https://doi.org/10.1007/BF01580381). Panics if there are no points.
+pub fn project_hull<const N: usize>(
+    points: &[Vector<f32, N>],
+    x: Vector<f32, N>,
+) -> Vector<f32, N> {
+    let points = points
+        .iter()
+        .map(|p| (p - x).cast::<f64>())
+        .collect::<Vec<_>>();
+    let tolerance = points.iter().map(|p| p.norm_squared()).fold(0.0, f64::max) * 1e-12;
+    let combine = |corral: &[(usize, f64)]| {
+        corral
+            .iter()
+            .fold(Vector::<f64, N>::zeros(), |acc, &(i, w)| {
+                acc + points[i] * w
+            })
+    };
+    let first = (0..points.len())
+        .min_by(|&a, &b| {
+            points[a]
+                .norm_squared()
+                .total_cmp(&points[b].norm_squared())
+        })
+        .unwrap();
+    // The points whose affine hull contains the current point, along with their weights.
+    let mut corral: SmallVec<[(usize, f64); 8]> = SmallVec::new();
+    corral.push((first, 1.0));
+    let mut current = points[first];
+    'major: for _ in 0..points.len() + 64 {
+        let (next, dot) = (0..points.len())
+            .map(|i| (i, current.dot(&points[i])))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .unwrap();
+        if current.norm_squared() - dot <= tolerance || corral.iter().any(|&(i, _)| i == next) {
+            break;
+        }
+        corral.push((next, 0.0));
+        loop {
+            let Some(affine) = affine_minimizer(&points, &corral) else {
+                // The corral is degenerate, which only happens due to rounding.
+                corral.pop();
+                break 'major;
+            };
+            if affine.iter().all(|&w| w > 0.0) {
+                for ((_, w), v) in corral.iter_mut().zip(affine.iter()) {
+                    *w = *v;
+                }
+                current = combine(&corral);
+                break;
+            }
+            // Move towards the affine minimizer until the first weight reaches zero, and drop that point.
+            let (blocking, theta) = corral
+                .iter()
+                .zip(affine.iter())
+                .enumerate()
+                .filter(|(_, (_, &v))| v <= 0.0)
+                .map(|(k, (&(_, w), &v))| (k, w / (w - v)))
+                .min_by(|a, b| a.1.total_cmp(&b.1))
+                .unwrap();
+            for ((_, w), v) in corral.iter_mut().zip(affine.iter()) {
+                *w = theta * v + (1.0 - theta) * *w;
+            }
+            corral[blocking].1 = 0.0;
+            corral.retain(|(_, w)| *w > 0.0);
+            current = combine(&corral);
+        }
+    }
+    x + current.cast::<f32>()
+}
+
+// The weights summing to one of the point of least norm in the affine hull of the corral.
+fn affine_minimizer<const N: usize>(
+    points: &[Vector<f64, N>],
+    corral: &[(usize, f64)],
+) -> Option<DVector<f64>> {
+    let k = corral.len();
+    let matrix = DMatrix::from_fn(k + 1, k + 1, |r, c| match (r < k, c < k) {
+        (true, true) => points[corral[r].0].dot(&points[corral[c].0]),
+        (false, false) => 0.0,
+        _ => 1.0,
+    });
+    let mut rhs = DVector::zeros(k + 1);
+    rhs[k] = 1.0;
+    let solution = matrix.lu().solve(&rhs)?;
+    let weights = solution.rows(0, k).into_owned();
+    weights.iter().all(|w| w.is_finite()).then_some(weights)
+}
+
+/// An upper bound on the largest singular value of a matrix. The singular values are computed with an SVD,
+/// padded for rounding error and capped by the Frobenius norm, which is always at least as large.
+pub fn spectral_norm<const N: usize>(m: &SMatrix<f32, N, N>) -> f32 {
+    let m = DMatrix::from_fn(N, N, |r, c| m[(r, c)] as f64);
+    let largest = m.singular_values().max().min(m.norm());
+    ((largest * (1.0 + 1e-6)) as f32).next_up()
+}
+
+/// Estimates the gradient of `f` at the point with central differences of the given step size.
+pub fn central_difference<const N: usize>(
+    f: impl Fn(Vector<f32, N>) -> f32,
+    point: Vector<f32, N>,
+    epsilon: f32,
+) -> Vector<f32, N> {
+    Vector::from_fn(|i, _| {
+        let mut offset = Vector::<f32, N>::zeros();
+        offset[i] = epsilon;
+        (f(point + offset) - f(point - offset)) / (2.0 * epsilon)
+    })
+}
+
+/// Wraps `x` into `[start, start + period)`, or leaves it unchanged if the period isn't positive.
+pub fn wrap_coordinate(x: f32, start: f32, period: f32) -> f32 {
+    if period <= 0.0 {
+        return x;
+    }
+    let offset = (x - start).rem_euclid(period);
+    // Rounding can give exactly the period for tiny negative offsets.
+    if offset >= period {
+        start
+    } else {
+        start + offset
+    }
+}
+
+/// The gradient of the distance to the given nearest surface point, and whether the point is inside.
+pub fn surface_gradient<const N: usize>(
+    point: Vector<f32, N>,
+    (surface, inside): (Vector<f32, N>, bool),
+) -> Vector<f32, N> {
+    let grad = (point - surface).normalize();
+    if inside {
+        -grad
+    } else {
+        grad
+    }
+}
 
 pub fn foreach_grid_in_rect<const N: usize>(
     offset: Vector<f32, N>,
@@ -55,3 +246,57 @@ pub fn to_linear<const N: usize>(index: Vector<u32, N>, shape: Vector<u32, N>) -
         })
         .1
 }
+
+#[cfg(test)]
+mod tests {
+    use nalgebra::{Matrix3, Rotation3, Vector3};
+
+    use super::*;
+
+    #[test]
+    fn spectral_norm_is_a_tight_upper_bound() {
+        let a = Rotation3::from_euler_angles(0.3, -1.1, 0.7).into_inner();
+        let b = Rotation3::from_euler_angles(-0.8, 0.4, 2.0).into_inner();
+        let m = a * Matrix3::from_diagonal(&Vector3::new(0.5, 3.0, 2.9)) * b;
+        let norm = spectral_norm(&m);
+        assert!((3.0..=3.001).contains(&norm), "{norm}");
+    }
+
+    #[test]
+    fn min_norm_point() {
+        // The nearest point of the triangle is inside it, at the foot of the perpendicular from the origin.
+        let triangle = [
+            Vector3::new(1.0, 0.0, 0.0),
+            Vector3::new(0.0, 1.0, 0.0),
+            Vector3::new(0.0, 0.0, 1.0),
+        ];
+        let nearest = project_hull(&triangle, Vector3::zeros());
+        assert!(
+            (nearest - Vector3::repeat(1.0 / 3.0)).norm() < 1e-6,
+            "{nearest}"
+        );
+        // On an edge, with the opposite corner dropped from the corral.
+        let nearest = project_hull(&triangle, Vector3::new(2.0, 2.0, -3.0));
+        assert!(
+            (nearest - Vector3::new(0.5, 0.5, 0.0)).norm() < 1e-6,
+            "{nearest}"
+        );
+        // At a corner.
+        let nearest = project_hull(&triangle, Vector3::new(3.0, -1.0, -1.0));
+        assert!((nearest - triangle[0]).norm() < 1e-6, "{nearest}");
+
+        // Points inside the hull are their own nearest point, even with repeated and interior points.
+        let mut cube = (0..8)
+            .map(|i| Vector3::new((i & 1) as f32, (i >> 1 & 1) as f32, (i >> 2) as f32))
+            .collect::<Vec<_>>();
+        cube.extend_from_within(..);
+        cube.push(Vector3::repeat(0.5));
+        let inside = Vector3::new(0.2, 0.7, 0.9);
+        assert!((project_hull(&cube, inside) - inside).norm() < 1e-6);
+        let nearest = project_hull(&cube, Vector3::new(1.5, 0.5, -2.0));
+        assert!(
+            (nearest - Vector3::new(1.0, 0.5, 0.0)).norm() < 1e-6,
+            "{nearest}"
+        );
+    }
+}
@@ -0,0 +1,489 @@
+use std::fmt::{self, Display, Formatter};
+
+use bvh::Bvh;
+use nalgebra::Vector2;
+use path::{ring_tolerance, Segment};
+use shape::{FillRule, Polygon};
+
+use super::*;
+
+/// A problem with a ring of a [`Polygon`]. Rings are numbered with the straight rings first, followed by the curved ones.
+/// Edge `i` of a straight ring ends at vertex `i`, and edge `i` of a curved ring is its `i`th segment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolygonProblem {
+    EmptyRing {
+        ring: usize,
+    },
+    /// A straight ring with less than three distinct vertices.
+    TooFewVertices {
+        ring: usize,
+    },
+    NonFinite {
+        ring: usize,
+        edge: usize,
+    },
+    /// An edge of zero length, such as from a repeated vertex.
+    DegenerateEdge {
+        ring: usize,
+        edge: usize,
+    },
+    /// An edge which doesn't start where the previous one ended.
+    /// The first edge is compared with the last one, so a ring which isn't closed is reported there.
+    Disconnected {
+        ring: usize,
+        edge: usize,
+    },
+    /// Two edges which touch or cross, other than neighboring edges meeting at their shared vertex.
+    Intersection {
+        ring: usize,
+        edge: usize,
+        other_ring: usize,
+        other_edge: usize,
+    },
+}
+impl Display for PolygonProblem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match *self {
+            PolygonProblem::EmptyRing { ring } => write!(f, "ring {} is empty", ring),
+            PolygonProblem::TooFewVertices { ring } => {
+                write!(f, "ring {} has less than three distinct vertices", ring)
+            }
+            PolygonProblem::NonFinite { ring, edge } => {
+                write!(
+                    f,
+                    "edge {} of ring {} has a non-finite coordinate",
+                    edge, ring
+                )
+            }
+            PolygonProblem::DegenerateEdge { ring, edge } => {
+                write!(f, "edge {} of ring {} has zero length", edge, ring)
+            }
+            PolygonProblem::Disconnected { ring, edge } => {
+                write!(
+                    f,
+                    "edge {} of ring {} doesn't start where the previous edge ends",
+                    edge, ring
+                )
+            }
+            PolygonProblem::Intersection {
+                ring,
+                edge,
+                other_ring,
+                other_edge,
+            } => write!(
+                f,
+                "edge {} of ring {} intersects edge {} of ring {}",
+                edge, ring, other_edge, other_ring
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PolygonError {
+    pub problems: Vec<PolygonProblem>,
+}
+impl Display for PolygonError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid polygon: ")?;
+        for (i, problem) in self.problems.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}", problem)?;
+        }
+        Ok(())
+    }
+}
+impl std::error::Error for PolygonError {}
+
+// A straight piece of an edge, after flattening curves.
+#[derive(Debug, Clone, Copy)]
+struct Piece {
+    ring: usize,
+    edge: usize,
+    // The position of the piece in its ring.
+    index: usize,
+    a: Vector2<f32>,
+    b: Vector2<f32>,
+}
+
+impl Polygon<2> {
+    /// Checks that every ring is closed, and that no edges are degenerate or intersect.
+    /// Curves are flattened to check for intersections, so curves which nearly touch may be reported.
+    pub fn validate(&self) -> Result<(), PolygonError> {
+        let rings = self.rings();
+        let mut problems = vec![];
+        let mut pieces = vec![];
+        let tolerance = (self.max_bound() - self.min_bound()).amax() * 1e-4;
+        for (r, (ring, straight)) in rings.iter().enumerate() {
+            if ring.is_empty() {
+                problems.push(PolygonProblem::EmptyRing { ring: r });
+                continue;
+            }
+            if *straight && distinct_vertices(ring) < 3 {
+                problems.push(PolygonProblem::TooFewVertices { ring: r });
+                continue;
+            }
+            if let Some(edge) = ring.iter().position(|s| !is_finite(s)) {
+                problems.push(PolygonProblem::NonFinite { ring: r, edge });
+                continue;
+            }
+            let start = pieces.len();
+            let gap_tolerance = ring_tolerance(ring);
+            for (e, segment) in ring.iter().enumerate() {
+                let previous = ring[(e + ring.len() - 1) % ring.len()];
+                if (previous.end() - segment.start()).amax() > gap_tolerance {
+                    problems.push(PolygonProblem::Disconnected { ring: r, edge: e });
+                }
+                if is_degenerate(segment) {
+                    problems.push(PolygonProblem::DegenerateEdge { ring: r, edge: e });
+                    continue;
+                }
+                let mut a = segment.start();
+                for b in segment.flatten(tolerance) {
+                    pieces.push(Piece {
+                        ring: r,
+                        edge: e,
+                        index: pieces.len() - start,
+                        a,
+                        b,
+                    });
+                    a = b;
+                }
+            }
+        }
+
+        let ring_sizes = pieces.iter().fold(vec![0; rings.len()], |mut sizes, p| {
+            sizes[p.ring] += 1;
+            sizes
+        });
+        let neighbors = |p: &Piece, q: &Piece| {
+            let size = ring_sizes[p.ring];
+            p.ring == q.ring && ((p.index + 1) % size == q.index || (q.index + 1) % size == p.index)
+        };
+        let bounds = pieces
+            .iter()
+            .map(|p| (p.a.inf(&p.b), p.a.sup(&p.b)))
+            .collect::<Vec<_>>();
+        let bvh = Bvh::new(&bounds);
+        let mut intersections = vec![];
+        for (i, p) in pieces.iter().enumerate() {
+            let (min, max) = bounds[i];
+            bvh.visit(
+                |node| node.min <= max && node.max >= min,
+                |j| {
+                    let q = &pieces[j as usize];
+                    if j as usize <= i {
+                        return;
+                    }
+                    let intersects = if neighbors(p, q) {
+                        // Neighbors share a vertex, so they only intersect if they fold back onto each other.
+                        let (first, second) = if (p.index + 1) % ring_sizes[p.ring] == q.index {
+                            (p, q)
+                        } else {
+                            (q, p)
+                        };
+                        folds_back(first.a, first.b, second.b)
+                    } else {
+                        segments_intersect(p.a, p.b, q.a, q.b)
+                    };
+                    if intersects {
+                        intersections.push(((p.ring, p.edge), (q.ring, q.edge)));
+                    }
+                },
+            );
+        }
+        intersections.sort();
+        intersections.dedup();
+        problems.extend(intersections.into_iter().map(
+            |((ring, edge), (other_ring, other_edge))| PolygonProblem::Intersection {
+                ring,
+                edge,
+                other_ring,
+                other_edge,
+            },
+        ));
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(PolygonError { problems })
+        }
+    }
+    /// Adds a ring, and then checks the whole polygon with [`validate`](Self::validate).
+    pub fn try_add_polygon(self, polygon: &[Vector2<f32>]) -> Result<Self, PolygonError> {
+        let polygon = self.add_polygon(polygon);
+        polygon.validate().map(|_| polygon)
+    }
+    /// Removes degenerate edges and rings, and orients the rings so that outer boundaries are counterclockwise
+    /// and holes are clockwise. Intersections are left as they are.
+    ///
+    /// Under the winding [`FillRule`]s the orientation determines what is inside, so it is kept.
+    pub fn repair(&self) -> Self {
+        let mut rings = vec![];
+        for (ring, straight) in self.rings() {
+            if ring.iter().any(|s| !is_finite(s)) {
+                continue;
+            }
+            let ring = ring
+                .into_iter()
+                .filter(|s| !is_degenerate(s))
+                .collect::<Vec<_>>();
+            if ring.is_empty() || (straight && distinct_vertices(&ring) < 3) {
+                continue;
+            }
+            if signed_area(&ring) != 0.0 {
+                rings.push((ring, straight));
+            }
+        }
+        let depths = rings
+            .iter()
+            .enumerate()
+            .map(|(i, (ring, _))| {
+                let point = ring[0].start();
+                rings
+                    .iter()
+                    .enumerate()
+                    .filter(|&(j, (other, _))| {
+                        i != j && other.iter().map(|s| s.crossings(point)).sum::<i32>() % 2 != 0
+                    })
+                    .count()
+            })
+            .collect::<Vec<_>>();
+        let mut polygon = Polygon::new().with_fill_rule(self.fill_rule());
+        for ((ring, straight), depth) in rings.into_iter().zip(depths) {
+            let ring = if self.fill_rule() != FillRule::EvenOdd
+                || (signed_area(&ring) > 0.0) == (depth % 2 == 0)
+            {
+                ring
+            } else {
+                ring.iter().rev().map(|s| s.reverse()).collect()
+            };
+            polygon = if straight {
+                polygon.add_polygon(&ring.iter().map(|s| s.end()).collect::<Vec<_>>())
+            } else {
+                polygon.add_segments(&ring)
+            };
+        }
+        polygon
+    }
+
+    // Every ring as segments, along with whether it was made of straight edges.
+    fn rings(&self) -> Vec<(Vec<Segment<2>>, bool)> {
+        let straight = self.polygons().iter().map(|polygon| {
+            let edges = (0..polygon.len())
+                .map(|i| {
+                    Segment::Line(polygon[(i + polygon.len() - 1) % polygon.len()], polygon[i])
+                })
+                .collect();
+            (edges, true)
+        });
+        let curved = self.paths().iter().map(|path| (path.clone(), false));
+        straight.chain(curved).collect()
+    }
+}
+
+fn is_finite(segment: &Segment<2>) -> bool {
+    let finite = |p: &Vector2<f32>| p.iter().all(|x| x.is_finite());
+    match segment {
+        Segment::Line(a, b) => finite(a) && finite(b),
+        Segment::Arc {
+            center,
+            u,
+            v,
+            sweep,
+        } => finite(center) && finite(u) && finite(v) && sweep.is_finite(),
+        Segment::Quadratic(points) => points.iter().all(finite),
+        Segment::Cubic(points) => points.iter().all(finite),
+    }
+}
+
+fn is_degenerate(segment: &Segment<2>) -> bool {
+    match segment {
+        Segment::Line(a, b) => a == b,
+        Segment::Arc { u, sweep, .. } => *u == Vector2::zeros() || *sweep == 0.0,
+        Segment::Quadratic(points) => points.iter().all(|p| *p == points[0]),
+        Segment::Cubic(points) => points.iter().all(|p| *p == points[0]),
+    }
+}
+
+fn distinct_vertices(ring: &[Segment<2>]) -> usize {
+    let mut vertices = ring.iter().map(|s| s.end()).collect::<Vec<_>>();
+    vertices.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    vertices.dedup();
+    vertices.len()
+}
+
+// The area enclosed by the ring, which is positive if it is counterclockwise.
+fn signed_area(ring: &[Segment<2>]) -> f32 {
+    let (min, max) = ring.iter().fold(
+        (
+            Vector2::repeat(f32::INFINITY),
+            Vector2::repeat(f32::NEG_INFINITY),
+        ),
+        |(min, max), s| {
+            let (a, b) = s.bounds();
+            (min.inf(&a), max.sup(&b))
+        },
+    );
+    let tolerance = (max - min).amax() * 1e-4;
+    let mut area = 0.0;
+    for segment in ring {
+        let mut a = segment.start();
+        for b in segment.flatten(tolerance) {
+            area += a.perp(&b);
+            a = b;
+        }
+    }
+    area / 2.0
+}
+
+fn orientation(a: Vector2<f32>, b: Vector2<f32>, c: Vector2<f32>) -> f64 {
+    let (a, b, c) = (a.cast::<f64>(), b.cast::<f64>(), c.cast::<f64>());
+    (b - a).perp(&(c - a))
+}
+
+// Whether `a -> b -> c` turns back on itself along a line.
+fn folds_back(a: Vector2<f32>, b: Vector2<f32>, c: Vector2<f32>) -> bool {
+    orientation(a, b, c) == 0.0 && (b - a).dot(&(c - b)) < 0.0
+}
+
+// Whether the closed segments `ab` and `cd` have any point in common.
+fn segments_intersect(a: Vector2<f32>, b: Vector2<f32>, c: Vector2<f32>, d: Vector2<f32>) -> bool {
+    let (d1, d2) = (orientation(c, d, a), orientation(c, d, b));
+    let (d3, d4) = (orientation(a, b, c), orientation(a, b, d));
+    if d1 * d2 < 0.0 && d3 * d4 < 0.0 {
+        return true;
+    }
+    let within = |p: Vector2<f32>, q: Vector2<f32>, x: Vector2<f32>| {
+        x.x >= p.x.min(q.x) && x.x <= p.x.max(q.x) && x.y >= p.y.min(q.y) && x.y <= p.y.max(q.y)
+    };
+    (d1 == 0.0 && within(c, d, a))
+        || (d2 == 0.0 && within(c, d, b))
+        || (d3 == 0.0 && within(a, b, c))
+        || (d4 == 0.0 && within(a, b, d))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(min: Vector2<f32>, size: f32) -> Vec<Vector2<f32>> {
+        vec![
+            min,
+            min + Vector2::new(size, 0.0),
+            min + Vector2::repeat(size),
+            min + Vector2::new(0.0, size),
+        ]
+    }
+
+    fn problems(polygon: &Polygon<2>) -> Vec<PolygonProblem> {
+        polygon.validate().err().map_or(vec![], |e| e.problems)
+    }
+
+    #[test]
+    fn degenerate_rings() {
+        let (a, b) = (Vector2::new(5.0, 0.0), Vector2::new(6.0, 1.0));
+        let polygon = Polygon::new()
+            .add_polygon(&[])
+            .add_polygon(&[a, b, a])
+            .add_polygon(&square(Vector2::zeros(), 1.0));
+        assert_eq!(
+            problems(&polygon),
+            [
+                PolygonProblem::EmptyRing { ring: 0 },
+                PolygonProblem::TooFewVertices { ring: 1 },
+            ]
+        );
+
+        let mut repeated = square(Vector2::zeros(), 1.0);
+        repeated.insert(1, repeated[1]);
+        let polygon = Polygon::new().add_polygon(&repeated);
+        assert_eq!(
+            problems(&polygon),
+            [PolygonProblem::DegenerateEdge { ring: 0, edge: 2 }]
+        );
+        assert_eq!(polygon.repair().validate(), Ok(()));
+    }
+
+    #[test]
+    fn intersections() {
+        let bow_tie = [
+            Vector2::new(0.0, 0.0),
+            Vector2::new(1.0, 1.0),
+            Vector2::new(1.0, 0.0),
+            Vector2::new(0.0, 1.0),
+        ];
+        assert_eq!(
+            problems(&Polygon::new().add_polygon(&bow_tie)),
+            [PolygonProblem::Intersection {
+                ring: 0,
+                edge: 1,
+                other_ring: 0,
+                other_edge: 3,
+            }]
+        );
+
+        let polygon = Polygon::new()
+            .try_add_polygon(&square(Vector2::zeros(), 2.0))
+            .unwrap();
+        let error = polygon
+            .try_add_polygon(&square(Vector2::repeat(1.0), 2.0))
+            .unwrap_err();
+        assert_eq!(
+            error.problems,
+            [
+                PolygonProblem::Intersection {
+                    ring: 0,
+                    edge: 2,
+                    other_ring: 1,
+                    other_edge: 1,
+                },
+                PolygonProblem::Intersection {
+                    ring: 0,
+                    edge: 3,
+                    other_ring: 1,
+                    other_edge: 0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn disconnected_curve() {
+        let polygon = Polygon::new().add_segments(&[
+            Segment::Line(Vector2::zeros(), Vector2::x()),
+            Segment::Quadratic([Vector2::x(), Vector2::repeat(1.0), Vector2::y()]),
+            Segment::Line(Vector2::new(-0.1, 1.0), Vector2::zeros()),
+        ]);
+        assert_eq!(
+            problems(&polygon),
+            [PolygonProblem::Disconnected { ring: 0, edge: 2 }]
+        );
+    }
+
+    #[test]
+    fn repair_orientation() {
+        let reversed = |ring: Vec<Vector2<f32>>| ring.into_iter().rev().collect::<Vec<_>>();
+        // A clockwise outer ring around a counterclockwise hole.
+        let polygon = Polygon::new()
+            .add_polygon(&reversed(square(Vector2::zeros(), 3.0)))
+            .add_polygon(&square(Vector2::repeat(1.0), 1.0));
+        let areas = |polygon: &Polygon<2>| {
+            polygon
+                .rings()
+                .iter()
+                .map(|(ring, _)| signed_area(ring))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(areas(&polygon), [-9.0, 1.0]);
+        assert_eq!(areas(&polygon.repair()), [9.0, -1.0]);
+        for fill_rule in [FillRule::NonZero, FillRule::Positive] {
+            let polygon = polygon.clone().with_fill_rule(fill_rule);
+            let repaired = polygon.repair();
+            assert_eq!(repaired.fill_rule(), fill_rule);
+            assert_eq!(areas(&repaired), [-9.0, 1.0]);
+        }
+    }
+}